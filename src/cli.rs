@@ -0,0 +1,231 @@
+//! Command line arguments, parsed by hand since there are only a few of them
+
+use std::{
+    io::{self, Error, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    data::{Canvas, MAX_SIZE},
+    export::{self, AnsiOptions, Format},
+    file,
+};
+
+pub const USAGE: &str = "\
+usage:
+  termdraw [file]                        draw, saving to file (drawing.termdraw by default)
+  termdraw new [--width W] [--height H] [file]
+                                         draw on a new canvas of the given size
+  termdraw export <file> --format <fmt> [-o <out>]
+                                         convert a drawing, fmt is txt, ans, ans16, html, svg
+                                         or png, ans and ans16 take +sauce for classic CP437
+                                         art with a SAUCE record, out defaults to file with
+                                         the format's extension, - writes to stdout
+  termdraw view <file>                   print a drawing to the terminal
+";
+
+pub enum Cli {
+    Help,
+    /// Draw, opening `path` first if it exists
+    Edit(Option<PathBuf>),
+    /// Draw on an empty canvas, sized to the terminal where no size is given
+    New {
+        width: Option<u16>,
+        height: Option<u16>,
+        path: Option<PathBuf>,
+    },
+    Export {
+        input: PathBuf,
+        format: Format,
+        output: Option<PathBuf>,
+    },
+    View(PathBuf),
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Cli, String> {
+    let mut args = args.into_iter();
+    let mut positional = vec![];
+    let mut width = None;
+    let mut height = None;
+    let mut format = None;
+    let mut output = None;
+    let mut help = false;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => help = true,
+            "--width" => width = Some(number(&value(&arg)?)?),
+            "--height" => height = Some(number(&value(&arg)?)?),
+            "--format" | "-f" => {
+                let name = value(&arg)?;
+                format = Some(Format::parse(&name).ok_or(format!("unknown format {:?}", name))?);
+            }
+            "--output" | "-o" => output = Some(PathBuf::from(value(&arg)?)),
+            // A lone - is a path, stdout for -o
+            flag if flag.starts_with('-') && flag != "-" => {
+                return Err(format!("unknown option {}", flag))
+            }
+            _ => positional.push(arg),
+        }
+    }
+    if help {
+        return Ok(Cli::Help);
+    }
+
+    // Options only make sense for the subcommand they belong to
+    let unexpected = |given: bool, name: &str| {
+        if given {
+            Err(format!("{} doesn't work here", name))
+        } else {
+            Ok(())
+        }
+    };
+    let (format_given, output_given) = (format.is_some(), output.is_some());
+    let mut positional = positional.into_iter();
+    let command = positional.next();
+    let cli = match command.as_deref() {
+        Some("new") => Cli::New {
+            width,
+            height,
+            path: positional.next().map(PathBuf::from),
+        },
+        Some("export") => Cli::Export {
+            input: positional.next().ok_or("export needs a file")?.into(),
+            format: format.ok_or("export needs a --format")?,
+            output,
+        },
+        Some("view") => Cli::View(positional.next().ok_or("view needs a file")?.into()),
+        path => Cli::Edit(path.map(PathBuf::from)),
+    };
+    if !matches!(cli, Cli::Export { .. }) {
+        unexpected(format_given, "--format")?;
+        unexpected(output_given, "--output")?;
+    }
+    if !matches!(cli, Cli::New { .. }) {
+        unexpected(width.is_some(), "--width")?;
+        unexpected(height.is_some(), "--height")?;
+    }
+    match positional.next() {
+        Some(extra) => Err(format!("unexpected argument {:?}", extra)),
+        None => Ok(cli),
+    }
+}
+
+fn number(arg: &str) -> Result<u16, String> {
+    match arg.parse() {
+        Ok(n @ 1..=MAX_SIZE) => Ok(n),
+        _ => Err(format!(
+            "{:?} isn't a valid size, it goes from 1 to {}",
+            arg, MAX_SIZE
+        )),
+    }
+}
+
+pub fn export(input: &Path, format: Format, output: Option<&Path>) -> io::Result<()> {
+    let canvas = open(input)?;
+    let output = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| input.with_extension(format.extension()));
+    if output == Path::new("-") {
+        return io::stdout().write_all(&export::export(&canvas, format)?);
+    }
+    // Exporting a .txt drawing as txt would write over it otherwise
+    if output == input {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{} would overwrite the drawing", output.display()),
+        ));
+    }
+    export::export_to(&canvas, format, &output)
+}
+
+/// Prints the drawing with its colors, like `cat` does for ANSI art
+pub fn view(path: &Path) -> io::Result<()> {
+    let canvas = open(path)?;
+    let options = AnsiOptions {
+        truecolor: true,
+        sauce: false,
+    };
+    let out = String::from_utf8_lossy(&export::ansi(&canvas, options)).replace("\r\n", "\n");
+    io::stdout().write_all(out.as_bytes())
+}
+
+// io errors don't say which file they're about
+fn open(path: &Path) -> io::Result<Canvas> {
+    file::open(path).map_err(|e| Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn error(line: &str) -> String {
+        match parse(args(line)) {
+            Ok(_) => panic!("{:?} should not parse", line),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn commands() {
+        assert!(matches!(parse(args("")), Ok(Cli::Edit(None))));
+        assert!(
+            matches!(parse(args("x.termdraw")), Ok(Cli::Edit(Some(path))) if path == Path::new("x.termdraw"))
+        );
+        assert!(matches!(parse(args("view --help")), Ok(Cli::Help)));
+        assert!(matches!(
+            parse(args("new --width 10 out")),
+            Ok(Cli::New {
+                width: Some(10),
+                height: None,
+                path: Some(_)
+            })
+        ));
+        assert!(matches!(
+            parse(args("export a.txt --format ans16+sauce -o -")),
+            Ok(Cli::Export {
+                format: Format::Ansi(AnsiOptions { truecolor: false, sauce: true }),
+                output: Some(output),
+                ..
+            }) if output == Path::new("-")
+        ));
+        assert!(matches!(parse(args("view a.txt")), Ok(Cli::View(_))));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("--bogus"), "unknown option --bogus");
+        assert_eq!(error("new --width"), "--width needs a value");
+        assert_eq!(
+            error("new --width 0"),
+            "\"0\" isn't a valid size, it goes from 1 to 4096"
+        );
+        assert_eq!(
+            error("new --height 70000"),
+            "\"70000\" isn't a valid size, it goes from 1 to 4096"
+        );
+        assert!(error("new --height 5000").starts_with("\"5000\" isn't a valid size"));
+        assert_eq!(error("export"), "export needs a file");
+        assert_eq!(error("export a.txt"), "export needs a --format");
+        assert_eq!(error("export a.txt -f gif"), "unknown format \"gif\"");
+        assert_eq!(
+            error("export a.txt -f png+sauce"),
+            "unknown format \"png+sauce\""
+        );
+        assert_eq!(error("view"), "view needs a file");
+        assert_eq!(error("a.txt -o b.txt"), "--output doesn't work here");
+        assert_eq!(
+            error("view a.txt --format txt"),
+            "--format doesn't work here"
+        );
+        assert_eq!(
+            error("export a.txt -f txt --width 3"),
+            "--width doesn't work here"
+        );
+        assert_eq!(error("a.txt b.txt"), "unexpected argument \"b.txt\"");
+    }
+}
@@ -0,0 +1,33 @@
+//! The system clipboard, reached through the terminal so it works over SSH too
+
+use std::io::Stdout;
+
+use crossterm::{queue, style::Print};
+
+/// Puts `text` on the system clipboard with the OSC 52 escape sequence
+pub fn copy(stdout: &mut Stdout, text: &str) {
+    queue!(
+        stdout,
+        Print(format!("\x1b]52;c;{}\x07", base64(text.as_bytes())))
+    )
+    .unwrap();
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | (byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
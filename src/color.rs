@@ -0,0 +1,152 @@
+use crossterm::style::Color;
+
+/// Name of a color as it's written into files, e.g. `dark_red` or `#ff8800`
+pub fn color_name(color: Color) -> String {
+    use Color::*;
+    match color {
+        Reset => "reset".to_string(),
+        Black => "black".to_string(),
+        DarkGrey => "dark_grey".to_string(),
+        Red => "red".to_string(),
+        DarkRed => "dark_red".to_string(),
+        Green => "green".to_string(),
+        DarkGreen => "dark_green".to_string(),
+        Yellow => "yellow".to_string(),
+        DarkYellow => "dark_yellow".to_string(),
+        Blue => "blue".to_string(),
+        DarkBlue => "dark_blue".to_string(),
+        Magenta => "magenta".to_string(),
+        DarkMagenta => "dark_magenta".to_string(),
+        Cyan => "cyan".to_string(),
+        DarkCyan => "dark_cyan".to_string(),
+        White => "white".to_string(),
+        Grey => "grey".to_string(),
+        Rgb { r, g, b } => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        AnsiValue(n) => format!("ansi:{}", n),
+    }
+}
+
+/// Inverse of `color_name`
+pub fn parse_color(name: &str) -> Option<Color> {
+    use Color::*;
+    let color = match name {
+        "reset" => Reset,
+        "black" => Black,
+        "dark_grey" => DarkGrey,
+        "red" => Red,
+        "dark_red" => DarkRed,
+        "green" => Green,
+        "dark_green" => DarkGreen,
+        "yellow" => Yellow,
+        "dark_yellow" => DarkYellow,
+        "blue" => Blue,
+        "dark_blue" => DarkBlue,
+        "magenta" => Magenta,
+        "dark_magenta" => DarkMagenta,
+        "cyan" => Cyan,
+        "dark_cyan" => DarkCyan,
+        "white" => White,
+        "grey" => Grey,
+        _ => {
+            if let Some(hex) = name.strip_prefix('#') {
+                if hex.len() != 6 {
+                    return None;
+                }
+                let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+                Rgb {
+                    r: channel(0)?,
+                    g: channel(2)?,
+                    b: channel(4)?,
+                }
+            } else {
+                AnsiValue(name.strip_prefix("ansi:")?.parse().ok()?)
+            }
+        }
+    };
+    Some(color)
+}
+
+/// Approximate RGB value of a color, using the usual xterm values for the named ones
+pub fn to_rgb(color: Color) -> (u8, u8, u8) {
+    use Color::*;
+    match color {
+        Black => (0, 0, 0),
+        DarkRed => (128, 0, 0),
+        DarkGreen => (0, 128, 0),
+        DarkYellow => (128, 128, 0),
+        DarkBlue => (0, 0, 128),
+        DarkMagenta => (128, 0, 128),
+        DarkCyan => (0, 128, 128),
+        Grey | Reset => (192, 192, 192),
+        DarkGrey => (128, 128, 128),
+        Red => (255, 0, 0),
+        Green => (0, 255, 0),
+        Yellow => (255, 255, 0),
+        Blue => (0, 0, 255),
+        Magenta => (255, 0, 255),
+        Cyan => (0, 255, 255),
+        White => (255, 255, 255),
+        Rgb { r, g, b } => (r, g, b),
+        AnsiValue(n) => ansi_to_rgb(n),
+    }
+}
+
+/// The 16 standard terminal colors, in the order of their ANSI color numbers
+pub const ANSI_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::DarkRed,
+    Color::DarkGreen,
+    Color::DarkYellow,
+    Color::DarkBlue,
+    Color::DarkMagenta,
+    Color::DarkCyan,
+    Color::Grey,
+    Color::DarkGrey,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+];
+
+/// ANSI color number of one of the 16 named colors
+pub fn ansi_index(color: Color) -> Option<u8> {
+    ANSI_COLORS
+        .iter()
+        .position(|&c| c == color)
+        .map(|index| index as u8)
+}
+
+fn ansi_to_rgb(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..=15 => to_rgb(ANSI_COLORS[n as usize]),
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let n = n - 16;
+            (level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        _ => {
+            let v = 8 + (n - 232) * 10;
+            (v, v, v)
+        }
+    }
+}
+
+/// The color in `palette` that's closest to `color`
+pub fn nearest(color: Color, palette: &[Color]) -> Color {
+    let (r, g, b) = to_rgb(color);
+    palette
+        .iter()
+        .copied()
+        .min_by_key(|&candidate| {
+            let (pr, pg, pb) = to_rgb(candidate);
+            let dr = r as i32 - pr as i32;
+            let dg = g as i32 - pg as i32;
+            let db = b as i32 - pb as i32;
+            // Weighted towards green, which the eye is most sensitive to
+            2 * dr * dr + 4 * dg * dg + 3 * db * db
+        })
+        .unwrap_or(color)
+}
@@ -1,15 +1,87 @@
-use crate::modes::{BrushData, HexData, Mode, PencilData};
-use crate::{data::*, handlers::handle_keychar};
+use crate::modes::{
+    BrailleData, BrushData, ContentBrushData, EllipseData, FillData, HalfBlockData, HexData,
+    LineData, Mode, PencilData, PromptData, RectData, SelectData,
+};
+use crate::{
+    clipboard,
+    data::*,
+    dither::Dither,
+    export,
+    handlers::{handle_keyboard, handle_keychar, handle_mouse},
+    import::{self, ImageColor, ImageOptions},
+    ramp::Ramp,
+};
 use crossterm::{
     cursor,
-    event::Event,
+    event::{Event, KeyCode, KeyModifiers, MouseEventKind},
     queue,
-    terminal::{Clear, ClearType},
 };
-use std::io::Stdout;
+use std::{
+    io::Stdout,
+    path::{Path, PathBuf},
+};
 
 pub fn process_shortcuts(event: &Event, stdout: &mut Stdout, state: &mut State) {
-    handle_keychar(&event, |c| {
+    // CTRL shortcuts work everywhere, since no mode types them as text
+    handle_keyboard(event, |key| {
+        if !key.modifiers.contains(KeyModifiers::CONTROL) {
+            return;
+        }
+        state.command = match key.code {
+            KeyCode::Char('z') => {
+                state.virtual_display.undo();
+                Command::Undo
+            }
+            KeyCode::Char('y') => {
+                state.virtual_display.redo();
+                Command::Redo
+            }
+            KeyCode::Char('s') => state.save(),
+            KeyCode::Char('e') => state.export(),
+            KeyCode::Char('o') => state.open(state.path.clone()),
+            _ => state.command.clone(),
+        }
+    });
+
+    // Arrow keys and the scroll wheel move around the canvas, unless the mode uses them itself
+    if !matches!(state.mode, Mode::Insert | Mode::Prompt(_)) {
+        handle_keyboard(event, |key| {
+            let step = if key.modifiers.contains(KeyModifiers::SHIFT) {
+                8
+            } else {
+                1
+            };
+            let canvas = &mut state.virtual_display;
+            match key.code {
+                KeyCode::Left => canvas.pan(-step, 0),
+                KeyCode::Right => canvas.pan(step, 0),
+                KeyCode::Up => canvas.pan(0, -step),
+                KeyCode::Down => canvas.pan(0, step),
+                _ => {}
+            }
+        });
+    }
+    if !matches!(state.mode, Mode::Brush(_)) {
+        handle_mouse(event, |ev| {
+            let step = match ev.kind {
+                MouseEventKind::ScrollUp => -3,
+                MouseEventKind::ScrollDown => 3,
+                _ => return,
+            };
+            if ev.modifiers.contains(KeyModifiers::SHIFT) {
+                state.virtual_display.pan(step * 2, 0);
+            } else {
+                state.virtual_display.pan(0, step);
+            }
+        });
+    }
+
+    // Modes that read keys themselves would otherwise switch away on every letter
+    if state.mode.reads_keys() {
+        return;
+    }
+
+    handle_keychar(event, |c| {
         state.command = match c {
             'i' => {
                 queue!(stdout, cursor::Show).unwrap();
@@ -29,18 +101,71 @@ pub fn process_shortcuts(event: &Event, stdout: &mut Stdout, state: &mut State)
                 Command::Enter(state.mode.clone())
             }
             'c' => {
-                state.mode = Mode::ContentBrush;
+                state.mode = Mode::ContentBrush(ContentBrushData::default());
                 Command::Enter(state.mode.clone())
             }
             'q' => {
-                queue!(stdout, Clear(ClearType::All)).unwrap();
-                // state.history = vec![];
+                state.virtual_display.clear();
                 Command::Clear
             }
             'h' => {
                 state.mode = Mode::Hex(HexData::default());
                 Command::Enter(state.mode.clone())
             }
+            'l' => {
+                state.mode = Mode::Layers;
+                Command::Enter(state.mode.clone())
+            }
+            'n' => {
+                state.mode = Mode::Line(LineData::default());
+                Command::Enter(state.mode.clone())
+            }
+            'r' => {
+                state.mode = Mode::Rect(RectData::default());
+                Command::Enter(state.mode.clone())
+            }
+            'o' => {
+                state.mode = Mode::Ellipse(EllipseData::default());
+                Command::Enter(state.mode.clone())
+            }
+            'f' => {
+                state.mode = Mode::Fill(FillData::default());
+                Command::Enter(state.mode.clone())
+            }
+            's' => {
+                state.mode = Mode::Select(SelectData::default());
+                Command::Enter(state.mode.clone())
+            }
+            'v' => {
+                state.mode = Mode::HalfBlock(HalfBlockData::default());
+                Command::Enter(state.mode.clone())
+            }
+            'a' => {
+                state.mode = Mode::Braille(BrailleData::default());
+                Command::Enter(state.mode.clone())
+            }
+            'Y' => {
+                clipboard::copy(stdout, &export::text(&state.virtual_display));
+                Command::Copy
+            }
+            'x' => {
+                std::mem::swap(&mut state.color, &mut state.bg_color);
+                state.command.clone()
+            }
+            'p' => {
+                state.target = state.target.next();
+                state.command.clone()
+            }
+            'B' | 'I' | 'U' | 'R' | 'D' => {
+                if let Some((attribute, _)) = ATTRIBUTES.iter().find(|(_, key)| *key == c) {
+                    state.attributes.toggle(*attribute);
+                }
+                state.command.clone()
+            }
+            ':' => {
+                state.mode = Mode::Prompt(PromptData::default());
+                Command::Enter(state.mode.clone())
+            }
             // 'f' => {
             //     // TODO: support rgb color
             //     let n = state
@@ -52,23 +177,106 @@ pub fn process_shortcuts(event: &Event, stdout: &mut Stdout, state: &mut State)
             //     state.color = state.colors[index];
             //     Command::Undo
             // }
-            // 'u' => {
-            //     queue!(stdout, Clear(ClearType::All)).unwrap();
-            //     // let undo = state.history.pop();
-            //     // if undo.is_some() {
-            //     // state.redo_layers.push(undo.unwrap());
-            //     // }
-            //     // frame_state.need_repaint = true;
-            //     Command::Undo
-            // }
-            // 'y' => {
-            //     // let redo = state.redo_layers.pop();
-            //     // if redo.is_some() {
-            //     //     state.history.push(redo.unwrap());
-            //     // }
-            //     Command::Redo
-            // }
+            'u' => {
+                state.virtual_display.undo();
+                Command::Undo
+            }
+            'y' => {
+                state.virtual_display.redo();
+                Command::Redo
+            }
             _ => state.command.clone(),
         }
     });
 }
+
+/// Runs a command typed into the `:` prompt
+pub fn run_command(input: &str, state: &mut State) -> Command {
+    let input = input.trim();
+    let (name, arg) = match input.split_once(' ') {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (input, ""),
+    };
+    match name {
+        "e" | "open" if !arg.is_empty() => state.open(PathBuf::from(arg)),
+        "w" | "save" => {
+            if !arg.is_empty() {
+                state.path = PathBuf::from(arg);
+            }
+            state.save()
+        }
+        "image" if !arg.is_empty() => image(arg, state),
+        "ramp" if !arg.is_empty() => match Ramp::parse(arg) {
+            Some(ramp) => {
+                state.ramp = ramp;
+                Command::None
+            }
+            None => Command::Error(format!("bad ramp {}", arg)),
+        },
+        "rename" if !arg.is_empty() => {
+            state.virtual_display.rename_plane(arg);
+            Command::None
+        }
+        "size" => {
+            // An empty canvas can't be drawn on or exported
+            let parse = |n: &str| {
+                let n = n.trim().parse::<u16>().ok()?;
+                (1..=MAX_SIZE).contains(&n).then_some(n)
+            };
+            let size = arg
+                .split_once(' ')
+                .and_then(|(w, h)| Some((parse(w)?, parse(h)?)));
+            match size {
+                Some((width, height)) => {
+                    let (width, height) = (width as usize, height as usize);
+                    state.virtual_display.resize(width, height);
+                    Command::Resize(width, height)
+                }
+                None => Command::Error(format!(
+                    "usage: size <width> <height>, from 1 to {}",
+                    MAX_SIZE
+                )),
+            }
+        }
+        _ => Command::Error(format!("unknown command {}", input)),
+    }
+}
+
+// `image [--color|--palette] [--dither <method>] <path>`
+fn image(mut arg: &str, state: &mut State) -> Command {
+    let mut options = ImageOptions {
+        color: ImageColor::Mono,
+        dither: Dither::None,
+    };
+    while let Some((flag, rest)) = arg.split_once(' ') {
+        let rest = rest.trim_start();
+        arg = match flag {
+            "--color" => {
+                options.color = ImageColor::True;
+                rest
+            }
+            "--palette" => {
+                options.color = ImageColor::Palette;
+                rest
+            }
+            "--dither" => {
+                let (name, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+                match Dither::parse(name) {
+                    Some(dither) => options.dither = dither,
+                    None => return Command::Error(format!("unknown dither {}", name)),
+                }
+                rest.trim_start()
+            }
+            _ => break,
+        };
+    }
+    let canvas = &mut state.virtual_display;
+    let (width, height) = canvas.size();
+    match import::image(Path::new(arg), width, height, &state.ramp, options) {
+        Ok(cells) => {
+            canvas.paste(0, 0, &cells);
+            Command::Load
+        }
+        Err(e) => Command::Error(e.to_string()),
+    }
+}
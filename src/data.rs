@@ -1,28 +1,45 @@
 use crate::{
-    handlers::{self, get_click_pos, handle_click},
+    export::{self, Format},
+    file,
+    history::{Edit, History, Step},
+    import,
     modes::{self, Mode},
+    ramp::Ramp,
 };
 use crossterm::{
-    cursor::{self, position},
-    event::Event,
+    event::{Event, MouseEvent},
     queue,
-    style::{Color, Print, SetBackgroundColor, SetForegroundColor},
+    style::{Attribute, Attributes, Color, Print, SetBackgroundColor, SetForegroundColor},
+    terminal,
 };
-use std::{fmt, fmt::Display, io::Stdout};
+use std::{collections::HashMap, fmt, fmt::Display, io::Stdout, path::PathBuf};
 
 pub struct State {
     pub repaint_counter: u32,
     pub mode: Mode,
     pub color: Color,
+    pub bg_color: Color,
+    pub attributes: Attributes,
+    pub target: PaintTarget,
     pub pos: (u16, u16),
     pub command: Command,
-    pub drag_pos: (u16, u16),
-    pub colors: Vec<Color>,
+    /// Where the mouse was at the last event of a stroke, `None` when the stroke didn't
+    /// start with a press in the current mode
+    pub drag_pos: Option<(u16, u16)>,
     pub pad: usize,
     // pub history: Vec<HistoryPage>,
     // pub ui: UI,
     pub virtual_display: Canvas,
     // pub redo_layers: Vec<HistoryPage>,
+    pub path: PathBuf,
+    pub export_format: Format,
+    /// Cells copied out of a selection
+    pub clipboard: Clip,
+    /// The ramp tools shade with unless they have one of their own
+    pub ramp: Ramp,
+    /// A file that was about to be opened over unsaved changes, and the version of the
+    /// drawing back then. Opening it again without changing anything goes ahead.
+    pub discard: Option<(PathBuf, usize)>,
 }
 
 impl State {
@@ -30,96 +47,703 @@ impl State {
         match &self.mode {
             Mode::Command => {}
             Mode::Insert => {
-                modes::insert(&event, stdout, self);
+                modes::insert(event, stdout, self);
             }
             Mode::Pencil(_) => {
-                modes::pencil(&event, self);
+                modes::pencil(event, self);
             }
-            Mode::ContentBrush => {
-                modes::content_brush(&event, stdout, self);
+            Mode::ContentBrush(_) => {
+                modes::content_brush(event, stdout, self);
             }
             Mode::Eyedropper => {
-                modes::eyedropper(&event, self);
+                modes::eyedropper(event, self);
             }
             Mode::Brush(_) => {
-                modes::brush(&event, stdout, self);
+                modes::brush(event, stdout, self);
             }
             Mode::Hex(_) => {
-                modes::hex(&event, self);
+                modes::hex(event, self);
+            }
+            Mode::Prompt(_) => {
+                modes::prompt(event, self);
+            }
+            Mode::Layers => {
+                modes::layers(event, self);
+            }
+            Mode::Line(_) => {
+                modes::line(event, self);
+            }
+            Mode::Rect(_) => {
+                modes::rect(event, self);
+            }
+            Mode::Ellipse(_) => {
+                modes::ellipse(event, self);
+            }
+            Mode::Fill(_) => {
+                modes::fill(event, self);
+            }
+            Mode::Select(_) => {
+                modes::select(event, stdout, self);
+            }
+            Mode::HalfBlock(_) => {
+                modes::half_block(event, self);
+            }
+            Mode::Braille(_) => {
+                modes::braille(event, self);
             }
         }
     }
 
+    pub fn save(&mut self) -> Command {
+        match file::save(&self.virtual_display, &self.path) {
+            Ok(()) => {
+                self.virtual_display.mark_saved();
+                Command::Save
+            }
+            Err(e) => Command::Error(e.to_string()),
+        }
+    }
+
+    pub fn open(&mut self, path: PathBuf) -> Command {
+        let canvas = &self.virtual_display;
+        let discard = (path.clone(), canvas.version());
+        if canvas.is_modified() && self.discard.as_ref() != Some(&discard) {
+            self.discard = Some(discard);
+            return Command::Unsaved;
+        }
+        self.discard = None;
+        let imported = import::can_import(&path);
+        match file::open(&path) {
+            Ok(mut canvas) => {
+                let (width, height) = terminal::size().unwrap_or_default();
+                canvas.resize_viewport(width, height);
+                self.virtual_display = canvas;
+                // Imported art gets saved next to the original instead of overwriting it
+                self.path = if imported {
+                    path.with_extension("termdraw")
+                } else {
+                    path
+                };
+                Command::Load
+            }
+            Err(e) => Command::Error(e.to_string()),
+        }
+    }
+
+    pub fn export(&mut self) -> Command {
+        let path = self.path.with_extension(self.export_format.extension());
+        match export::export_to(&self.virtual_display, self.export_format, &path) {
+            Ok(()) => Command::Export(self.export_format),
+            Err(e) => Command::Error(e.to_string()),
+        }
+    }
+
+    /// Paints `brush` with the current colors and attributes, leaving alone
+    /// whatever the paint target says to keep
+    pub fn stroke(&mut self, col: u16, row: u16, brush: char) {
+        let layer = self.paint(col, row, brush);
+        self.virtual_display.set(col, row, layer);
+    }
+
+    /// What `stroke` would turn the cell into
+    pub fn paint(&self, col: u16, row: u16, brush: char) -> Layer {
+        let base = self.virtual_display.cell(col, row).unwrap_or_default();
+        match self.target {
+            PaintTarget::Fg => Layer {
+                brush,
+                brush_color: self.color,
+                attributes: self.attributes,
+                ..base
+            },
+            PaintTarget::Bg => Layer {
+                bg_color: self.bg_color,
+                ..base
+            },
+            PaintTarget::Both => Layer {
+                brush,
+                brush_color: self.color,
+                bg_color: self.bg_color,
+                attributes: self.attributes,
+                changed: true,
+            },
+        }
+    }
+
+    /// Shows what stroking `cells` would look like, without changing the canvas yet
+    pub fn preview(&mut self, cells: &[(u16, u16, char)]) {
+        let preview = cells
+            .iter()
+            .map(|&(col, row, brush)| ((col as usize, row as usize), self.paint(col, row, brush)))
+            .collect();
+        self.virtual_display.set_preview(preview);
+    }
+
     pub fn eyedrop(&mut self, col: u16, row: u16) {
-        self.color = self
-            .virtual_display
-            .get(col, row)
-            .and_then(|el| Some(el.brush_color))
-            .unwrap_or_else(|| crossterm::style::Color::Black);
+        let Some(el) = self.virtual_display.get(col, row) else {
+            self.color = crossterm::style::Color::Black;
+            return;
+        };
+        if self.target != PaintTarget::Bg {
+            self.color = el.brush_color;
+        }
+        if self.target != PaintTarget::Fg {
+            self.bg_color = el.bg_color;
+        }
     }
 }
 
+/// Largest width and height of a canvas, every cell costs memory in each layer
+pub const MAX_SIZE: u16 = 4096;
+
 pub struct Canvas {
+    /// What ends up on screen: the visible planes composited on top of each other
     pub vd: Vec<Vec<Layer>>,
     pub need_repaint: bool,
+    pub history: History,
+    /// How much of the canvas fits in the terminal, including the status bar row
+    pub viewport: (u16, u16),
+    /// Canvas position of the top left corner of the terminal
+    pub offset: (u16, u16),
+    pub need_clear: bool,
+    /// The layer stack, from the bottom up
+    pub planes: Vec<Plane>,
+    /// Index of the plane that gets drawn on
+    pub active: usize,
+    /// Cells drawn over the canvas while a tool is being dragged, e.g. the line tool
+    pub preview: HashMap<(usize, usize), Layer>,
+    /// Shown with inverted colors
+    pub selection: Option<Area>,
+    next_id: usize,
+    /// Goes up with every change to the drawing, `saved_version` is where it was saved
+    version: usize,
+    saved_version: usize,
 }
 
 impl Canvas {
     pub fn new(width: u16, height: u16) -> Canvas {
-        let width = width as usize;
-        let height = height as usize;
+        let width = width.min(MAX_SIZE) as usize;
+        let height = height.min(MAX_SIZE) as usize;
         let mut virtual_display = Vec::with_capacity(width);
         for _ in 0..width {
             let mut nested = Vec::with_capacity(height);
             for _ in 0..height {
-                nested.push(Layer {
-                    brush: ' ',
-                    brush_color: Color::White,
-                    changed: false,
-                })
+                nested.push(Layer::default())
             }
             virtual_display.push(nested);
         }
         Canvas {
             vd: virtual_display,
             need_repaint: false,
+            history: History::default(),
+            viewport: (width as u16, height as u16),
+            offset: (0, 0),
+            need_clear: false,
+            planes: vec![Plane::new(0, "layer 1", width, height)],
+            active: 0,
+            preview: HashMap::new(),
+            selection: None,
+            next_id: 1,
+            version: 0,
+            saved_version: 0,
         }
     }
     pub fn set(&mut self, col: u16, row: u16, layer: Layer) {
-        let col = col as usize;
-        let row = row as usize;
-        if col < self.vd.len() - 1 && row < self.vd[0].len() - 1 {
-            self.vd[col][row] = layer;
+        self.set_cell(col, row, Some(layer));
+    }
+    /// Makes a cell of the active plane transparent again
+    pub fn erase(&mut self, col: u16, row: u16) {
+        self.set_cell(col, row, None);
+    }
+    fn set_cell(&mut self, col: u16, row: u16, cell: Option<Layer>) {
+        let (col, row) = (col as usize, row as usize);
+        if col < self.vd.len() && row < self.vd[col].len() {
+            self.put(col, row, cell);
+        }
+    }
+    /// Follows the terminal size. The canvas itself keeps its size, so nothing
+    /// drawn outside of a smaller terminal gets lost.
+    pub fn resize_viewport(&mut self, width: u16, height: u16) {
+        self.viewport = (width, height);
+        self.pan(0, 0);
+        self.repaint_all();
+    }
+    /// Moves the viewport by `dx`, `dy` cells without leaving the canvas
+    pub fn pan(&mut self, dx: i32, dy: i32) {
+        let (width, height) = self.size();
+        let (view_width, view_height) = self.viewport;
+        // The last terminal row is taken up by the status bar
+        let max_x = width.saturating_sub(view_width.into());
+        let max_y = height.saturating_sub(view_height.saturating_sub(1).into());
+        let offset = (
+            (self.offset.0 as i32 + dx).clamp(0, max_x as i32) as u16,
+            (self.offset.1 as i32 + dy).clamp(0, max_y as i32) as u16,
+        );
+        if offset != self.offset {
+            self.offset = offset;
+            self.repaint_all();
+        }
+    }
+    /// Turns terminal coordinates of mouse events into canvas coordinates
+    pub fn translate(&self, event: &Event) -> Event {
+        match event {
+            Event::Mouse(ev) => Event::Mouse(MouseEvent {
+                column: ev.column.saturating_add(self.offset.0),
+                row: ev.row.saturating_add(self.offset.1),
+                ..*ev
+            }),
+            event => event.clone(),
+        }
+    }
+    // Writes a cell of the active plane and records the change so it can be undone
+    fn put(&mut self, col: usize, row: usize, cell: Option<Layer>) {
+        let plane = &mut self.planes[self.active];
+        if plane.locked || !plane.visible {
+            return;
+        }
+        let before = plane.cells[col][row];
+        if same_cell(&before, &cell) {
+            return;
+        }
+        plane.cells[col][row] = cell;
+        self.version += 1;
+        self.history.record(Edit {
+            plane: plane.id,
+            col,
+            row,
+            before,
+            after: cell,
+        });
+        self.composite(col, row);
+    }
+    /// Replaces the preview, cells outside of the canvas are dropped
+    pub fn set_preview(&mut self, mut preview: HashMap<(usize, usize), Layer>) {
+        let (width, height) = self.size();
+        preview.retain(|&(col, row), _| col < width && row < height);
+        let old = std::mem::replace(&mut self.preview, preview);
+        for &(col, row) in old.keys().chain(self.preview.keys()) {
+            self.vd[col][row].changed = true;
+            self.need_repaint = true;
+        }
+    }
+    pub fn clear_preview(&mut self) {
+        self.set_preview(HashMap::new());
+    }
+    /// Shows `cells` floating over the canvas with their top left corner at `col`, `row`
+    pub fn preview_clip(&mut self, col: u16, row: u16, cells: &Clip) {
+        let mut preview = HashMap::new();
+        for (x, column) in cells.iter().enumerate() {
+            for (y, cell) in column.iter().enumerate() {
+                if let Some(cell) = cell {
+                    preview.insert((col as usize + x, row as usize + y), *cell);
+                }
+            }
+        }
+        self.set_preview(preview);
+    }
+    pub fn set_selection(&mut self, selection: Option<Area>) {
+        let (width, height) = self.size();
+        for area in [self.selection, selection].iter().flatten() {
+            for (col, row) in area.cells() {
+                if (col as usize) < width && (row as usize) < height {
+                    self.vd[col as usize][row as usize].changed = true;
+                    self.need_repaint = true;
+                }
+            }
+        }
+        self.selection = selection;
+    }
+    /// The cells of the active plane inside `area`
+    pub fn copy(&self, area: Area) -> Clip {
+        (area.left..=area.right)
+            .map(|col| {
+                (area.top..=area.bottom)
+                    .map(|row| self.cell(col, row))
+                    .collect()
+            })
+            .collect()
+    }
+    pub fn erase_area(&mut self, area: Area) {
+        for (col, row) in area.cells() {
+            self.erase(col, row);
+        }
+    }
+    /// Draws `cells` with their top left corner at `col`, `row`, transparent cells are skipped
+    pub fn paste(&mut self, col: u16, row: u16, cells: &Clip) {
+        for (x, column) in cells.iter().enumerate() {
+            for (y, cell) in column.iter().enumerate() {
+                if let Some(cell) = cell {
+                    let (x, y) = (col as usize + x, row as usize + y);
+                    if x <= u16::MAX as usize && y <= u16::MAX as usize {
+                        self.set(x as u16, y as u16, *cell);
+                    }
+                }
+            }
+        }
+    }
+    /// Recomputes what's visible at a position from the plane stack
+    fn composite(&mut self, col: usize, row: usize) {
+        let cell = self
+            .planes
+            .iter()
+            .rev()
+            .filter(|plane| plane.visible)
+            .find_map(|plane| plane.cells[col][row])
+            .unwrap_or_default();
+        let shown = &mut self.vd[col][row];
+        if !shown.looks_like(&cell) {
+            *shown = cell;
+            shown.changed = true;
             self.need_repaint = true;
         }
     }
+    pub fn composite_all(&mut self) {
+        let (width, height) = self.size();
+        for col in 0..width {
+            for row in 0..height {
+                self.composite(col, row);
+            }
+        }
+    }
+    /// Clears the active plane
+    pub fn clear(&mut self) {
+        let (width, height) = self.size();
+        for col in 0..width {
+            for row in 0..height {
+                self.put(col, row, None);
+            }
+        }
+    }
+    pub fn undo(&mut self) {
+        // Nothing to undo doesn't change the drawing
+        let Some(step) = self.history.undo().cloned() else {
+            return;
+        };
+        self.version += 1;
+        match step {
+            Step::Cells(edits) => {
+                for edit in edits.iter().rev() {
+                    self.restore(edit.plane, edit.col, edit.row, edit.before);
+                }
+            }
+            Step::RemovePlane { index, plane } => {
+                self.active = index.min(self.planes.len());
+                self.planes.insert(self.active, plane);
+                self.composite_all();
+            }
+        }
+    }
+    pub fn redo(&mut self) {
+        let Some(step) = self.history.redo().cloned() else {
+            return;
+        };
+        self.version += 1;
+        match step {
+            Step::Cells(edits) => {
+                for edit in edits.iter() {
+                    self.restore(edit.plane, edit.col, edit.row, edit.after);
+                }
+            }
+            Step::RemovePlane { plane, .. } => {
+                if let Some(index) = self.planes.iter().position(|p| p.id == plane.id) {
+                    self.planes.remove(index);
+                    self.active = self.active.min(self.planes.len() - 1);
+                    self.composite_all();
+                }
+            }
+        }
+    }
+    // Like put, but without touching the history. Edits of deleted planes are dropped.
+    fn restore(&mut self, id: usize, col: usize, row: usize, cell: Option<Layer>) {
+        let Some(plane) = self.planes.iter_mut().find(|plane| plane.id == id) else {
+            return;
+        };
+        if let Some(target) = plane
+            .cells
+            .get_mut(col)
+            .and_then(|column| column.get_mut(row))
+        {
+            *target = cell;
+            self.composite(col, row);
+        }
+    }
+    /// Marks every cell as changed so the whole canvas gets drawn again
+    pub fn repaint_all(&mut self) {
+        for cell in self.vd.iter_mut().flatten() {
+            cell.changed = true;
+        }
+        self.need_repaint = true;
+        // Whatever is next to a canvas smaller than the terminal needs clearing too
+        self.need_clear = true;
+    }
+    pub fn size(&self) -> (usize, usize) {
+        (self.vd.len(), self.vd.first().map_or(0, Vec::len))
+    }
+    /// Changes the size of the canvas, cutting off whatever doesn't fit anymore
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let width = width.min(MAX_SIZE as usize);
+        let height = height.min(MAX_SIZE as usize);
+        self.version += 1;
+        self.preview.clear();
+        self.selection = None;
+        for column in self.vd.iter_mut() {
+            column.resize(height, Layer::default());
+        }
+        self.vd.resize(width, vec![Layer::default(); height]);
+        for plane in self.planes.iter_mut() {
+            for column in plane.cells.iter_mut() {
+                column.resize(height, None);
+            }
+            plane.cells.resize(width, vec![None; height]);
+        }
+        self.pan(0, 0);
+        self.repaint_all();
+    }
+    /// The cell of the active plane, `None` if it's transparent or out of bounds
+    pub fn cell(&self, col: u16, row: u16) -> Option<Layer> {
+        let plane = &self.planes[self.active];
+        *plane.cells.get(col as usize)?.get(row as usize)?
+    }
     pub fn get(&self, col: u16, row: u16) -> Option<&Layer> {
         let col = col as usize;
         let row = row as usize;
         self.vd.get(col)?.get(row)
     }
+    /// Adds an empty plane above the active one and makes it active
+    pub fn add_plane(&mut self) {
+        self.version += 1;
+        let (width, height) = self.size();
+        let name = format!("layer {}", self.next_id + 1);
+        self.planes.insert(
+            self.active + 1,
+            Plane::new(self.next_id, &name, width, height),
+        );
+        self.next_id += 1;
+        self.active += 1;
+    }
+    /// Deletes the active plane, which can be undone like any other edit
+    pub fn remove_plane(&mut self) {
+        if self.planes.len() > 1 {
+            self.version += 1;
+            let plane = self.planes.remove(self.active);
+            self.history.push(Step::RemovePlane {
+                index: self.active,
+                plane,
+            });
+            self.active = self.active.min(self.planes.len() - 1);
+            self.composite_all();
+        }
+    }
+    /// Makes the plane `delta` steps up (or down) the stack active
+    pub fn select_plane(&mut self, delta: i32) {
+        let last = self.planes.len() as i32 - 1;
+        self.active = (self.active as i32 + delta).clamp(0, last) as usize;
+    }
+    /// Moves the active plane `delta` steps up (or down) the stack
+    pub fn move_plane(&mut self, delta: i32) {
+        self.version += 1;
+        let last = self.planes.len() as i32 - 1;
+        let target = (self.active as i32 + delta).clamp(0, last) as usize;
+        let plane = self.planes.remove(self.active);
+        self.planes.insert(target, plane);
+        self.active = target;
+        self.composite_all();
+    }
+    /// Gives planes ids from 0 up, e.g. after they were replaced wholesale
+    pub fn reset_plane_ids(&mut self) {
+        for (id, plane) in self.planes.iter_mut().enumerate() {
+            plane.id = id;
+        }
+        self.next_id = self.planes.len();
+        self.active = self.active.min(self.planes.len().saturating_sub(1));
+    }
+    pub fn toggle_visible(&mut self) {
+        self.version += 1;
+        let plane = &mut self.planes[self.active];
+        plane.visible = !plane.visible;
+        self.composite_all();
+    }
+    pub fn toggle_locked(&mut self) {
+        self.version += 1;
+        let plane = &mut self.planes[self.active];
+        plane.locked = !plane.locked;
+    }
+    pub fn rename_plane(&mut self, name: &str) {
+        self.version += 1;
+        self.planes[self.active].name = name.to_string();
+    }
+    pub fn version(&self) -> usize {
+        self.version
+    }
+    /// Whether anything changed since the drawing was last saved or loaded
+    pub fn is_modified(&self) -> bool {
+        self.version != self.saved_version
+    }
+    pub fn mark_saved(&mut self) {
+        self.saved_version = self.version;
+    }
+}
+
+/// Cells cut out of a plane, column by column like `Plane::cells`
+pub type Clip = Vec<Vec<Option<Layer>>>;
+
+/// A rectangle of cells, the edges included
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub left: u16,
+    pub top: u16,
+    pub right: u16,
+    pub bottom: u16,
+}
+
+impl Area {
+    /// The area with opposite corners `a` and `b`
+    pub fn new(a: (u16, u16), b: (u16, u16)) -> Area {
+        Area {
+            left: a.0.min(b.0),
+            top: a.1.min(b.1),
+            right: a.0.max(b.0),
+            bottom: a.1.max(b.1),
+        }
+    }
+    /// The area `cells` covers when its top left corner is at `col`, `row`
+    pub fn of_clip(col: u16, row: u16, cells: &Clip) -> Area {
+        let width = cells.len().max(1) as u16;
+        let height = cells.first().map_or(1, Vec::len).max(1) as u16;
+        Area {
+            left: col,
+            top: row,
+            right: col.saturating_add(width - 1),
+            bottom: row.saturating_add(height - 1),
+        }
+    }
+    pub fn contains(&self, col: u16, row: u16) -> bool {
+        (self.left..=self.right).contains(&col) && (self.top..=self.bottom).contains(&row)
+    }
+    pub fn size(&self) -> (u16, u16) {
+        (self.right - self.left + 1, self.bottom - self.top + 1)
+    }
+    pub fn cells(&self) -> impl Iterator<Item = (u16, u16)> {
+        let rows = self.top..=self.bottom;
+        (self.left..=self.right).flat_map(move |col| rows.clone().map(move |row| (col, row)))
+    }
+}
+
+/// One sheet in the layer stack. `None` cells are transparent and show the planes below.
+#[derive(Clone)]
+pub struct Plane {
+    pub id: usize,
+    pub name: String,
+    pub cells: Vec<Vec<Option<Layer>>>,
+    pub visible: bool,
+    pub locked: bool,
 }
 
-#[derive(Copy, Clone)]
+impl Plane {
+    pub fn new(id: usize, name: &str, width: usize, height: usize) -> Plane {
+        Plane {
+            id,
+            name: name.to_string(),
+            cells: vec![vec![None; height]; width],
+            visible: true,
+            locked: false,
+        }
+    }
+}
+
+fn same_cell(a: &Option<Layer>, b: &Option<Layer>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.looks_like(b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
 pub struct Layer {
     pub brush: char,
     pub brush_color: Color,
+    pub bg_color: Color,
+    pub attributes: Attributes,
     pub changed: bool,
 }
 
-#[derive(Copy, Clone)]
-pub struct TextLayer {
-    pub brush: char,
-    pub color: Color,
+impl Layer {
+    /// Compares what would end up on screen, ignoring the repaint flag
+    pub fn looks_like(&self, other: &Layer) -> bool {
+        self.brush == other.brush
+            && self.brush_color == other.brush_color
+            && self.bg_color == other.bg_color
+            && self.attributes == other.attributes
+    }
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        Layer {
+            brush: ' ',
+            brush_color: Color::White,
+            bg_color: Color::Reset,
+            attributes: Attributes::default(),
+            changed: false,
+        }
+    }
+}
+
+/// The text attributes cells can have, with the key that toggles them
+pub const ATTRIBUTES: [(Attribute, char); 5] = [
+    (Attribute::Bold, 'B'),
+    (Attribute::Italic, 'I'),
+    (Attribute::Underlined, 'U'),
+    (Attribute::Reverse, 'R'),
+    (Attribute::Dim, 'D'),
+];
+
+/// Which parts of a cell the drawing tools change
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaintTarget {
+    Fg,
+    Bg,
+    Both,
+}
+
+impl PaintTarget {
+    pub fn next(&self) -> PaintTarget {
+        match self {
+            PaintTarget::Fg => PaintTarget::Bg,
+            PaintTarget::Bg => PaintTarget::Both,
+            PaintTarget::Both => PaintTarget::Fg,
+        }
+    }
+}
+
+impl Display for PaintTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PaintTarget::Fg => "FG",
+                PaintTarget::Bg => "BG",
+                PaintTarget::Both => "FG+BG",
+            }
+        )
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum Command {
     Enter(Mode),
-    _Save,
+    Save,
+    Load,
+    Export(Format),
+    Copy,
+    /// Opening a file was held back since it'd throw away unsaved changes
+    Unsaved,
+    Resize(usize, usize),
+    Error(String),
     Clear,
-    _Undo,
-    _Redo,
+    Undo,
+    Redo,
     None,
     _Hex,
 }
@@ -131,11 +755,17 @@ impl Display for Command {
             "{}",
             match self {
                 Command::Enter(mode) => format!("ENTER {}", mode),
+                Command::Save => "SAVED".to_string(),
+                Command::Load => "LOADED".to_string(),
+                Command::Export(format) => format!("EXPORTED {}", format),
+                Command::Copy => "COPIED".to_string(),
+                Command::Unsaved => "UNSAVED CHANGES, OPEN AGAIN TO DISCARD THEM".to_string(),
+                Command::Resize(width, height) => format!("SIZE {}x{}", width, height),
+                Command::Error(e) => format!("ERROR: {}", e),
                 Command::Clear => "CLEAR".to_string(),
-                Command::None => "REDO".to_string(),
                 // Command::Hex => "HEX".to_string(),
-                // Command::Undo => "UNDO".to_string(),
-                // Command::Redo => "REDO".to_string(),
+                Command::Undo => "UNDO".to_string(),
+                Command::Redo => "REDO".to_string(),
                 _ => "".to_string(),
             }
         )
@@ -154,20 +784,16 @@ pub struct UI<'a> {
 }
 
 impl<'a> UI<'a> {
-    pub fn render(&mut self, max: (usize, usize), bg_color: Color) -> usize {
+    pub fn render(&mut self, max: (usize, usize)) -> usize {
         let (max_width, _max_height) = max;
         let used_width: usize = self.elements.iter().map(|el| el.get_width()).sum();
-        let free_space = if used_width > max_width {
-            0
-        } else {
-            max_width - used_width
-        };
+        let free_space = max_width.saturating_sub(used_width);
         self.pad = free_space / (self.elements.len() - 1);
         let final_pad_len = free_space % (self.elements.len() - 1);
         let len = self.elements.len();
 
         for (i, element) in self.elements.iter_mut().enumerate() {
-            element.paint(self.stdout, bg_color);
+            element.paint(self.stdout, self.bg_color);
             let pad = if i + 1 == len {
                 final_pad_len
             } else {
@@ -277,3 +903,26 @@ impl Widget {
         width + 2
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_without_history() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.undo();
+        canvas.redo();
+        assert!(!canvas.is_modified());
+
+        let brush = Layer {
+            brush: 'x',
+            ..Default::default()
+        };
+        canvas.set(0, 0, brush);
+        canvas.history.commit();
+        canvas.mark_saved();
+        canvas.undo();
+        assert!(canvas.is_modified());
+    }
+}
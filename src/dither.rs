@@ -0,0 +1,109 @@
+//! Spreading out the error of rounding values to a few levels, so gradients still
+//! look like gradients when there are only so many characters or colors to pick from
+
+use std::{fmt, fmt::Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dither {
+    None,
+    /// Ordered dithering with a 4x4 Bayer matrix
+    Bayer,
+    FloydSteinberg,
+    Atkinson,
+}
+
+impl Dither {
+    pub fn parse(name: &str) -> Option<Dither> {
+        let dither = match name {
+            "none" => Dither::None,
+            "bayer" | "ordered" => Dither::Bayer,
+            "floyd" | "floyd-steinberg" => Dither::FloydSteinberg,
+            "atkinson" => Dither::Atkinson,
+            _ => return None,
+        };
+        Some(dither)
+    }
+
+    /// Where the error of a pixel goes, relative to it, and how much of it
+    fn kernel(&self) -> &'static [(i32, i32, f64)] {
+        match self {
+            Dither::None | Dither::Bayer => &[],
+            Dither::FloydSteinberg => &[
+                (1, 0, 7.0 / 16.0),
+                (-1, 1, 3.0 / 16.0),
+                (0, 1, 5.0 / 16.0),
+                (1, 1, 1.0 / 16.0),
+            ],
+            // Only passes on 6/8 of the error, which keeps more contrast
+            Dither::Atkinson => &[
+                (1, 0, 1.0 / 8.0),
+                (2, 0, 1.0 / 8.0),
+                (-1, 1, 1.0 / 8.0),
+                (0, 1, 1.0 / 8.0),
+                (1, 1, 1.0 / 8.0),
+                (0, 2, 1.0 / 8.0),
+            ],
+        }
+    }
+}
+
+impl Display for Dither {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Dither::None => "NONE",
+                Dither::Bayer => "BAYER",
+                Dither::FloydSteinberg => "FLOYD-STEINBERG",
+                Dither::Atkinson => "ATKINSON",
+            }
+        )
+    }
+}
+
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// The ordered dithering threshold at a position, between 0 and 1
+pub fn bayer(col: usize, row: usize) -> f64 {
+    (BAYER[row % 4][col % 4] as f64 + 0.5) / 16.0
+}
+
+/// Rounds every value of a grid (stored column by column) with `quantize`, which returns
+/// the index of the level it picked and the value of that level. `spread` is how far
+/// apart the levels are, which is how much ordered dithering nudges values around.
+pub fn dither<const N: usize, F>(
+    mut values: Vec<Vec<[f64; N]>>,
+    method: Dither,
+    spread: f64,
+    quantize: F,
+) -> Vec<Vec<usize>>
+where
+    F: Fn([f64; N]) -> (usize, [f64; N]),
+{
+    let width = values.len();
+    let height = values.first().map_or(0, Vec::len);
+    let mut out = vec![vec![0; height]; width];
+    for row in 0..height {
+        for col in 0..width {
+            let mut value = values[col][row];
+            if method == Dither::Bayer {
+                let nudge = (bayer(col, row) - 0.5) * spread;
+                value = value.map(|channel| channel + nudge);
+            }
+            let (index, picked) = quantize(value);
+            out[col][row] = index;
+            for &(dx, dy, weight) in method.kernel() {
+                let (x, y) = (col as i32 + dx, row as i32 + dy);
+                if x < 0 || x as usize >= width || y as usize >= height {
+                    continue;
+                }
+                let target = &mut values[x as usize][y as usize];
+                for channel in 0..N {
+                    target[channel] += (value[channel] - picked[channel]) * weight;
+                }
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,194 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crossterm::style::{Attribute, Color};
+
+use super::runs::{runs, Style};
+use crate::{
+    color::{ansi_index, nearest, ANSI_COLORS},
+    data::{Canvas, ATTRIBUTES},
+    generate_colors, import,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnsiOptions {
+    /// Keep RGB colors as they are instead of picking the closest palette color
+    pub truecolor: bool,
+    /// Write classic ANSI art: CP437 text, bold for bright colors and a SAUCE record with
+    /// the dimensions of the drawing
+    pub sauce: bool,
+}
+
+/// Escape-sequence art that can be `cat`ed into a terminal
+pub fn ansi(canvas: &Canvas, options: AnsiOptions) -> Vec<u8> {
+    let palette = generate_colors();
+    let convert = |color| match color {
+        color if options.truecolor => color,
+        Color::Reset => Color::Reset,
+        color => nearest(color, &palette),
+    };
+    let encode = |text: &str| -> Vec<u8> {
+        if options.sauce {
+            // Classic viewers read CP437, not UTF-8
+            text.chars()
+                .map(|c| import::to_cp437(c).unwrap_or(b'?'))
+                .collect()
+        } else {
+            text.bytes().collect()
+        }
+    };
+    let mut out = vec![];
+    for row in runs(canvas) {
+        let mut current: Option<Style> = None;
+        for run in row {
+            let style = Style {
+                fg: convert(run.style.fg),
+                bg: convert(run.style.bg),
+                ..run.style
+            };
+            let style = if options.sauce {
+                bold_for_bright(style)
+            } else {
+                style
+            };
+            out.extend(sgr(style, current).bytes());
+            current = Some(style);
+            out.extend(encode(&run.text));
+        }
+        if current.is_some_and(|current| current != Style::default()) {
+            out.extend(b"\x1b[0m");
+        }
+        out.extend(b"\r\n");
+    }
+    if options.sauce {
+        let (width, height) = canvas.size();
+        let record = sauce(width, height, out.len());
+        out.extend(record);
+    }
+    out
+}
+
+/// Classic viewers only know the 8 dark colors. Bold makes the text color bright, and
+/// blink the background with iCE colors, which the SAUCE record turns on.
+fn bold_for_bright(style: Style) -> Style {
+    let mut attributes = style.attributes;
+    attributes.unset(Attribute::Bold);
+    let mut dark = |color, attribute| match ansi_index(color) {
+        Some(index @ 8..) => {
+            attributes.set(attribute);
+            ANSI_COLORS[index as usize - 8]
+        }
+        _ => color,
+    };
+    Style {
+        fg: dark(style.fg, Attribute::Bold),
+        bg: dark(style.bg, Attribute::SlowBlink),
+        attributes,
+    }
+}
+
+/// The escape sequence that switches from `current` to `style`
+fn sgr(style: Style, current: Option<Style>) -> String {
+    let current = current.unwrap_or_default();
+    let mut params = vec![];
+    // Blink only ever comes from bold_for_bright
+    let attributes = ATTRIBUTES
+        .iter()
+        .map(|(attribute, _)| *attribute)
+        .chain([Attribute::SlowBlink]);
+    // Attributes can only be turned off all at once, which resets the colors too
+    let reset = attributes
+        .clone()
+        .any(|attribute| current.attributes.has(attribute) && !style.attributes.has(attribute));
+    let current = if reset {
+        params.push("0".to_string());
+        Style::default()
+    } else {
+        current
+    };
+    for attribute in attributes {
+        if style.attributes.has(attribute) && !current.attributes.has(attribute) {
+            params.push(attribute.sgr());
+        }
+    }
+    if style.fg != current.fg {
+        params.push(foreground(style.fg));
+    }
+    if style.bg != current.bg {
+        params.push(background(style.bg));
+    }
+    if params.is_empty() {
+        return String::new();
+    }
+    format!("\x1b[{}m", params.join(";"))
+}
+
+/// SGR parameters that set `color` as the foreground
+fn foreground(color: Color) -> String {
+    match color {
+        Color::Reset => "39".to_string(),
+        Color::Rgb { r, g, b } => format!("38;2;{};{};{}", r, g, b),
+        Color::AnsiValue(n) => format!("38;5;{}", n),
+        named => match ansi_index(named).unwrap_or(7) {
+            n @ 0..=7 => (30 + n).to_string(),
+            n => (90 + n - 8).to_string(),
+        },
+    }
+}
+
+/// SGR parameters that set `color` as the background
+fn background(color: Color) -> String {
+    match color {
+        Color::Reset => "49".to_string(),
+        Color::Rgb { r, g, b } => format!("48;2;{};{};{}", r, g, b),
+        Color::AnsiValue(n) => format!("48;5;{}", n),
+        named => match ansi_index(named).unwrap_or(0) {
+            n @ 0..=7 => (40 + n).to_string(),
+            n => (100 + n - 8).to_string(),
+        },
+    }
+}
+
+/// The 128 byte SAUCE metadata record, see <https://www.acid.org/info/sauce/sauce.htm>
+fn sauce(width: usize, height: usize, file_size: usize) -> Vec<u8> {
+    fn field(out: &mut Vec<u8>, value: &str, len: usize) {
+        let bytes = value.as_bytes();
+        out.extend(bytes.iter().take(len));
+        out.resize(out.len() + len.saturating_sub(bytes.len()), b' ');
+    }
+
+    // The record is preceded by an EOF character so `type` on DOS stops before it
+    let mut out = vec![0x1a];
+    out.extend(b"SAUCE00");
+    field(&mut out, "", 35); // title
+    field(&mut out, "", 20); // author
+    field(&mut out, "", 20); // group
+    field(&mut out, &today(), 8);
+    out.extend((file_size as u32).to_le_bytes());
+    out.push(1); // data type: character
+    out.push(1); // file type: ANSi
+    out.extend((width.min(u16::MAX as usize) as u16).to_le_bytes());
+    out.extend((height.min(u16::MAX as usize) as u16).to_le_bytes());
+    out.extend([0; 4]); // tinfo 3 and 4
+    out.push(0); // comments
+    out.push(1); // flags: iCE colors, blink means a bright background
+    out.extend([0; 22]); // font name
+    out
+}
+
+/// Today's date as CCYYMMDD
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86_400) as i64;
+    // Howard Hinnant's civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}{:02}{:02}", year, month, day)
+}
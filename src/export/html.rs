@@ -0,0 +1,63 @@
+use crossterm::style::Attribute;
+
+use super::{
+    runs::{escape, hex, runs, Style},
+    DEFAULT_BG, DEFAULT_FG,
+};
+use crate::data::Canvas;
+
+/// A standalone page with the drawing in a `<pre>`, colored with inline styles
+pub fn html(canvas: &Canvas) -> String {
+    let mut pre = String::new();
+    for row in runs(canvas) {
+        for run in row {
+            let text = escape(&run.text);
+            match css(run.style) {
+                css if css.is_empty() || run.is_blank() => pre += &text,
+                css => pre += &format!("<span style=\"{}\">{}</span>", css, text),
+            }
+        }
+        pre.push('\n');
+    }
+    format!(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>termdraw</title>\n\
+         </head>\n\
+         <body>\n\
+         <pre style=\"display: inline-block; padding: 0.5em; background: {}; color: {}; \
+         font-family: monospace; line-height: 1.2\">\n{}</pre>\n\
+         </body>\n\
+         </html>\n",
+        hex(DEFAULT_BG),
+        hex(DEFAULT_FG),
+        pre
+    )
+}
+
+fn css(style: Style) -> String {
+    let (fg, bg) = style.colors();
+    let mut css = vec![];
+    if let Some(fg) = fg {
+        css.push(format!("color: {}", hex(fg)));
+    }
+    if let Some(bg) = bg {
+        css.push(format!("background: {}", hex(bg)));
+    }
+    let attributes = style.attributes;
+    if attributes.has(Attribute::Bold) {
+        css.push("font-weight: bold".to_string());
+    }
+    if attributes.has(Attribute::Italic) {
+        css.push("font-style: italic".to_string());
+    }
+    if attributes.has(Attribute::Underlined) {
+        css.push("text-decoration: underline".to_string());
+    }
+    if attributes.has(Attribute::Dim) {
+        css.push("opacity: 0.5".to_string());
+    }
+    css.join("; ")
+}
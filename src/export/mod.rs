@@ -0,0 +1,125 @@
+mod ansi;
+mod html;
+mod png;
+mod runs;
+mod svg;
+mod text;
+
+use std::{fmt, fmt::Display, fs, io, path::Path};
+
+use crate::data::Canvas;
+
+pub use ansi::{ansi, AnsiOptions};
+pub use html::html;
+pub use png::png;
+pub use svg::svg;
+pub use text::{area_text, text};
+
+// What the default colors of a terminal look like
+const DEFAULT_FG: (u8, u8, u8) = (192, 192, 192);
+const DEFAULT_BG: (u8, u8, u8) = (0, 0, 0);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Ansi(AnsiOptions),
+    Png,
+    Html,
+    Svg,
+}
+
+impl Format {
+    /// The formats the export button cycles through
+    pub const ALL: [Format; 7] = [
+        Format::Text,
+        Format::Ansi(AnsiOptions {
+            truecolor: true,
+            sauce: false,
+        }),
+        Format::Ansi(AnsiOptions {
+            truecolor: false,
+            sauce: false,
+        }),
+        // What ANSI art viewers and archives expect
+        Format::Ansi(AnsiOptions {
+            truecolor: false,
+            sauce: true,
+        }),
+        Format::Png,
+        Format::Html,
+        Format::Svg,
+    ];
+
+    /// The format for a name given on the command line. ANSI formats take a `+sauce`
+    /// suffix for classic CP437 art with a SAUCE record, e.g. `ans16+sauce`.
+    pub fn parse(name: &str) -> Option<Format> {
+        let name = name.to_lowercase();
+        let (name, sauce) = match name.strip_suffix("+sauce") {
+            Some(name) => (name, true),
+            None => (name.as_str(), false),
+        };
+        let ansi = |truecolor| Format::Ansi(AnsiOptions { truecolor, sauce });
+        let format = match name {
+            "ans" | "ansi" => ansi(true),
+            "ans16" => ansi(false),
+            _ if sauce => return None,
+            "txt" | "text" => Format::Text,
+            "png" => Format::Png,
+            "html" => Format::Html,
+            "svg" => Format::Svg,
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    pub fn next(&self) -> Format {
+        let index = Format::ALL
+            .iter()
+            .position(|format| format == self)
+            .map_or(0, |index| index + 1);
+        Format::ALL[index % Format::ALL.len()]
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Text => "txt",
+            Format::Ansi(_) => "ans",
+            Format::Png => "png",
+            Format::Html => "html",
+            Format::Svg => "svg",
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Format::Text => "TXT",
+            Format::Ansi(options) if options.truecolor => "ANS",
+            Format::Ansi(_) => "ANS16",
+            Format::Png => "PNG",
+            Format::Html => "HTML",
+            Format::Svg => "SVG",
+        };
+        write!(f, "{}", name)?;
+        match self {
+            Format::Ansi(options) if options.sauce => write!(f, "+SAUCE"),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub fn export(canvas: &Canvas, format: Format) -> io::Result<Vec<u8>> {
+    let out = match format {
+        Format::Text => text(canvas).into_bytes(),
+        Format::Ansi(options) => ansi(canvas, options),
+        Format::Png => png(canvas)?,
+        Format::Html => html(canvas).into_bytes(),
+        Format::Svg => svg(canvas).into_bytes(),
+    };
+    Ok(out)
+}
+
+pub fn export_to(canvas: &Canvas, format: Format, path: &Path) -> io::Result<()> {
+    fs::write(path, export(canvas, format)?)
+}
@@ -0,0 +1,83 @@
+use std::io::{self, Cursor, Error, ErrorKind};
+
+use crossterm::style::{Attribute, Color};
+use font8x8::legacy::{BASIC_LEGACY, BLOCK_LEGACY, BOX_LEGACY, LATIN_LEGACY};
+use image::{ImageFormat, Rgb, RgbImage};
+
+use super::{DEFAULT_BG, DEFAULT_FG};
+use crate::{color::to_rgb, data::Canvas, shapes};
+
+// Glyphs are 8x8, drawn twice as tall to get the usual shape of a terminal cell
+const CELL_WIDTH: u32 = 8;
+const CELL_HEIGHT: u32 = 16;
+
+/// A picture of the canvas, drawn with a built in bitmap font
+pub fn png(canvas: &Canvas) -> io::Result<Vec<u8>> {
+    let (width, height) = canvas.size();
+    // PNG has no way to store an image without pixels
+    if width == 0 || height == 0 {
+        return Err(Error::new(ErrorKind::InvalidInput, "the canvas is empty"));
+    }
+    let mut picture = RgbImage::new(width as u32 * CELL_WIDTH, height as u32 * CELL_HEIGHT);
+    for (col, column) in canvas.vd.iter().enumerate() {
+        for (row, cell) in column.iter().enumerate() {
+            let mut fg = match cell.brush_color {
+                Color::Reset => DEFAULT_FG,
+                color => to_rgb(color),
+            };
+            let mut bg = match cell.bg_color {
+                Color::Reset => DEFAULT_BG,
+                color => to_rgb(color),
+            };
+            if cell.attributes.has(Attribute::Reverse) {
+                std::mem::swap(&mut fg, &mut bg);
+            }
+            let underline = cell.attributes.has(Attribute::Underlined);
+            let glyph = glyph(cell.brush);
+            for y in 0..CELL_HEIGHT {
+                let bits = glyph[(y / 2) as usize];
+                for x in 0..CELL_WIDTH {
+                    let ink = bits & 1 << x != 0 || underline && y == CELL_HEIGHT - 1;
+                    let (r, g, b) = if ink { fg } else { bg };
+                    let (px, py) = (col as u32 * CELL_WIDTH + x, row as u32 * CELL_HEIGHT + y);
+                    picture.put_pixel(px, py, Rgb([r, g, b]));
+                }
+            }
+        }
+    }
+    let mut out = Cursor::new(vec![]);
+    picture
+        .write_to(&mut out, ImageFormat::Png)
+        .map_err(io::Error::other)?;
+    Ok(out.into_inner())
+}
+
+/// The 8 rows of a character, the lowest bit of each row is its leftmost pixel
+fn glyph(c: char) -> [u8; 8] {
+    let code = c as usize;
+    match code {
+        0x20..=0x7f => BASIC_LEGACY[code],
+        0xa0..=0xff => LATIN_LEGACY[code - 0xa0],
+        0x2500..=0x257f => BOX_LEGACY[code - 0x2500],
+        0x2580..=0x259f => BLOCK_LEGACY[code - 0x2580],
+        _ => match shapes::braille_dots(c) {
+            Some(dots) => braille(dots),
+            // Control characters and whatever the font doesn't have
+            None if c.is_control() => [0; 8],
+            None => BASIC_LEGACY['?' as usize],
+        },
+    }
+}
+
+// Each of the 2x4 dots becomes a 2x1 block, which is 2x2 pixels once stretched
+fn braille(dots: u8) -> [u8; 8] {
+    let mut rows = [0; 8];
+    for (x, column) in shapes::BRAILLE_DOTS.iter().enumerate() {
+        for (y, bit) in column.iter().enumerate() {
+            if dots & bit != 0 {
+                rows[y * 2] |= 0b11 << (1 + x * 4);
+            }
+        }
+    }
+    rows
+}
@@ -0,0 +1,279 @@
+use crossterm::style::{Attribute, Attributes, Color};
+
+use super::{DEFAULT_BG, DEFAULT_FG};
+use crate::{color::to_rgb, data::Canvas};
+
+type Rgb = (u8, u8, u8);
+
+/// Cells next to each other on a row that all look the same
+pub struct Run {
+    pub col: usize,
+    pub text: String,
+    pub style: Style,
+}
+
+impl Run {
+    pub fn is_spaces(&self) -> bool {
+        self.text.chars().all(|c| c == ' ')
+    }
+
+    /// Whether the run looks like empty cells, so it doesn't need any styling
+    pub fn is_blank(&self) -> bool {
+        self.is_spaces() && self.style.bg == Color::Reset && self.style.attributes.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub attributes: Attributes,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            fg: Color::Reset,
+            bg: Color::Reset,
+            attributes: Attributes::default(),
+        }
+    }
+}
+
+impl Style {
+    /// Whether the text color shows even on a space, as the background or an underline
+    fn colors_spaces(&self) -> bool {
+        self.attributes.has(Attribute::Reverse) || self.attributes.has(Attribute::Underlined)
+    }
+
+    /// The text and background colors, `None` where the default one will do
+    pub fn colors(&self) -> (Option<Rgb>, Option<Rgb>) {
+        let rgb = |color| (color != Color::Reset).then(|| to_rgb(color));
+        let (fg, bg) = (rgb(self.fg), rgb(self.bg));
+        if self.attributes.has(Attribute::Reverse) {
+            (
+                Some(bg.unwrap_or(DEFAULT_BG)),
+                Some(fg.unwrap_or(DEFAULT_FG)),
+            )
+        } else {
+            (fg, bg)
+        }
+    }
+}
+
+/// The runs of every row, with trailing blank cells and rows left out
+pub fn runs(canvas: &Canvas) -> Vec<Vec<Run>> {
+    let (width, height) = canvas.size();
+    let mut rows: Vec<Vec<Run>> = Vec::with_capacity(height);
+    for row in 0..height {
+        let mut runs: Vec<Run> = vec![];
+        for col in 0..width {
+            let cell = &canvas.vd[col][row];
+            let brush = if cell.brush.is_control() {
+                ' '
+            } else {
+                cell.brush
+            };
+            let mut style = Style {
+                fg: cell.brush_color,
+                bg: cell.bg_color,
+                attributes: cell.attributes,
+            };
+            match runs.last_mut() {
+                // The color of a space isn't visible, so it doesn't need to break up a run
+                Some(run) if brush == ' ' && !style.colors_spaces() && style.fg != run.style.fg => {
+                    style.fg = run.style.fg;
+                    if style == run.style {
+                        run.text.push(brush);
+                        continue;
+                    }
+                }
+                // Same goes for spaces before a character
+                Some(run)
+                    if run.is_spaces()
+                        && !run.style.colors_spaces()
+                        && Style {
+                            fg: run.style.fg,
+                            ..style
+                        } == run.style =>
+                {
+                    run.style.fg = style.fg;
+                    run.text.push(brush);
+                    continue;
+                }
+                Some(run) if style == run.style => {
+                    run.text.push(brush);
+                    continue;
+                }
+                _ => {}
+            }
+            runs.push(Run {
+                col,
+                text: brush.to_string(),
+                style,
+            });
+        }
+        // Trailing spaces without a background or attributes don't show
+        while let Some(run) = runs.last_mut() {
+            if run.style.bg != Color::Reset || !run.style.attributes.is_empty() {
+                break;
+            }
+            let len = run.text.trim_end_matches(' ').len();
+            run.text.truncate(len);
+            if !run.text.is_empty() {
+                break;
+            }
+            runs.pop();
+        }
+        rows.push(runs);
+    }
+    while rows.last().is_some_and(Vec::is_empty) {
+        rows.pop();
+    }
+    rows
+}
+
+/// `text` with the characters that mean something in HTML and XML escaped
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out += "&amp;",
+            '<' => out += "&lt;",
+            '>' => out += "&gt;",
+            '"' => out += "&quot;",
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn hex((r, g, b): Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Layer;
+
+    fn canvas(rows: &[&[(char, Color, Attributes)]], width: u16) -> Canvas {
+        let mut canvas = Canvas::new(width, rows.len() as u16);
+        for (row, cells) in rows.iter().enumerate() {
+            for (col, &(brush, brush_color, attributes)) in cells.iter().enumerate() {
+                let layer = Layer {
+                    brush,
+                    brush_color,
+                    attributes,
+                    ..Default::default()
+                };
+                canvas.set(col as u16, row as u16, layer);
+            }
+        }
+        canvas
+    }
+
+    fn summary(rows: &[Vec<Run>]) -> Vec<Vec<(usize, &str, Color)>> {
+        rows.iter()
+            .map(|runs| {
+                runs.iter()
+                    .map(|run| (run.col, run.text.as_str(), run.style.fg))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn plain_spaces_join() {
+        let none = Attributes::default();
+        let canvas = canvas(
+            &[&[
+                ('a', Color::Red, none),
+                (' ', Color::Blue, none),
+                ('b', Color::Red, none),
+                (' ', Color::Green, none),
+                ('c', Color::Blue, none),
+            ]],
+            5,
+        );
+        // The spaces take the color of whatever they're next to
+        assert_eq!(
+            summary(&runs(&canvas)),
+            [[(0, "a b ", Color::Red), (4, "c", Color::Blue)]]
+        );
+    }
+
+    #[test]
+    fn reversed_spaces() {
+        let none = Attributes::default();
+        let reverse = Attributes::from(Attribute::Reverse);
+        let canvas = canvas(
+            &[&[
+                ('a', Color::Red, none),
+                (' ', Color::Blue, reverse),
+                (' ', Color::Green, reverse),
+                ('b', Color::Red, reverse),
+            ]],
+            4,
+        );
+        // Their text color is their background, so it has to stay
+        assert_eq!(
+            summary(&runs(&canvas)),
+            [[
+                (0, "a", Color::Red),
+                (1, " ", Color::Blue),
+                (2, " ", Color::Green),
+                (3, "b", Color::Red),
+            ]]
+        );
+    }
+
+    #[test]
+    fn underlined_spaces() {
+        let none = Attributes::default();
+        let underlined = Attributes::from(Attribute::Underlined);
+        let canvas = canvas(
+            &[&[
+                (' ', Color::Green, underlined),
+                ('a', Color::Red, underlined),
+                ('b', Color::Red, none),
+                (' ', Color::Blue, underlined),
+            ]],
+            4,
+        );
+        // The underline has the text color, even under a space
+        assert_eq!(
+            summary(&runs(&canvas)),
+            [[
+                (0, " ", Color::Green),
+                (1, "a", Color::Red),
+                (2, "b", Color::Red),
+                (3, " ", Color::Blue),
+            ]]
+        );
+    }
+
+    #[test]
+    fn trailing_blanks() {
+        let none = Attributes::default();
+        let reverse = Attributes::from(Attribute::Reverse);
+        let canvas = canvas(
+            &[
+                &[('a', Color::Red, none), (' ', Color::Red, none)],
+                &[],
+                &[(' ', Color::Red, none), (' ', Color::Blue, reverse)],
+                &[(' ', Color::Red, none)],
+            ],
+            4,
+        );
+        // Plain trailing spaces and rows go, but not the ones in between or that show
+        assert_eq!(
+            summary(&runs(&canvas)),
+            [
+                vec![(0, "a", Color::Red)],
+                vec![],
+                vec![(0, " ", Color::Red), (1, " ", Color::Blue)],
+            ]
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use crossterm::style::Attribute;
+
+use super::{
+    runs::{escape, hex, runs},
+    DEFAULT_BG, DEFAULT_FG,
+};
+use crate::data::Canvas;
+
+// Roughly the shape of a monospace cell at this font size
+const FONT_SIZE: f64 = 14.0;
+const CELL_WIDTH: f64 = 8.4;
+const CELL_HEIGHT: f64 = 17.0;
+// From the top of a cell to the baseline of its text
+const BASELINE: f64 = 13.0;
+
+/// A picture with a `<text>` per run, lined up on the cell grid
+pub fn svg(canvas: &Canvas) -> String {
+    let (width, height) = canvas.size();
+    let (width, height) = (width as f64 * CELL_WIDTH, height as f64 * CELL_HEIGHT);
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"0 0 {w} {h}\" font-family=\"monospace\" font-size=\"{}\" \
+         xml:space=\"preserve\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+        FONT_SIZE,
+        hex(DEFAULT_BG),
+        w = width,
+        h = height,
+    );
+    let mut texts = String::new();
+    for (row, runs) in runs(canvas).iter().enumerate() {
+        for run in runs {
+            let x = run.col as f64 * CELL_WIDTH;
+            let y = row as f64 * CELL_HEIGHT;
+            let len = run.text.chars().count() as f64 * CELL_WIDTH;
+            let (fg, bg) = run.style.colors();
+            // Backgrounds go first so no text ends up underneath one
+            if let Some(bg) = bg {
+                out += &format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                    x,
+                    y,
+                    len,
+                    CELL_HEIGHT,
+                    hex(bg)
+                );
+            }
+            if run.is_spaces() && !run.style.attributes.has(Attribute::Underlined) {
+                continue;
+            }
+            let mut attributes = format!(
+                "x=\"{}\" y=\"{}\" textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\" fill=\"{}\"",
+                x,
+                y + BASELINE,
+                len,
+                hex(fg.unwrap_or(DEFAULT_FG))
+            );
+            let style = run.style.attributes;
+            if style.has(Attribute::Bold) {
+                attributes += " font-weight=\"bold\"";
+            }
+            if style.has(Attribute::Italic) {
+                attributes += " font-style=\"italic\"";
+            }
+            if style.has(Attribute::Underlined) {
+                attributes += " text-decoration=\"underline\"";
+            }
+            if style.has(Attribute::Dim) {
+                attributes += " opacity=\"0.5\"";
+            }
+            texts += &format!("<text {}>{}</text>\n", attributes, escape(&run.text));
+        }
+    }
+    out + &texts + "</svg>\n"
+}
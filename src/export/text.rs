@@ -0,0 +1,33 @@
+use crate::data::{Area, Canvas};
+
+/// Plain UTF-8 text without any colors, with trailing whitespace and empty lines trimmed
+pub fn text(canvas: &Canvas) -> String {
+    let (width, height) = canvas.size();
+    if width == 0 || height == 0 {
+        return String::new();
+    }
+    area_text(
+        canvas,
+        Area::new((0, 0), (width as u16 - 1, height as u16 - 1)),
+    )
+}
+
+/// Like `text`, but only for the part of the canvas inside `area`
+pub fn area_text(canvas: &Canvas, area: Area) -> String {
+    let mut out = String::new();
+    for row in area.top..=area.bottom {
+        // vd is stored column by column, text goes row by row
+        let line: String = (area.left..=area.right)
+            .map(|col| canvas.get(col, row).map_or(' ', |cell| cell.brush))
+            .map(|brush| if brush.is_control() { ' ' } else { brush })
+            .collect();
+        out += line.trim_end();
+        out.push('\n');
+    }
+    let len = out.trim_end().len();
+    out.truncate(len);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,401 @@
+//! The native termdraw format. It's plain text so drawings can live in git:
+//!
+//! ```text
+//! termdraw 1
+//! size 80 24
+//! colors
+//! 0 white
+//! g #ff8800
+//! layer background
+//! hidden
+//! locked
+//! text
+//! <one line per row, one char per cell>
+//! color
+//! <one line per row, one color key per cell, space for transparent>
+//! background
+//! <one line per row, one color key per cell, space for none>
+//! attributes
+//! <one line per row, one base 32 bit set of ATTRIBUTES per cell, space for none>
+//! layer ...
+//! ```
+//!
+//! Layers are listed from the bottom up, `hidden`, `locked`, `background` and
+//! `attributes` are optional.
+//! The 16 palette colors always get the same keys (`0`-`f`), so changing the
+//! color of a cell only changes that cell's key in the diff.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Error, ErrorKind},
+    iter::Peekable,
+    path::Path,
+    str::Lines,
+};
+
+use crossterm::style::{Attributes, Color};
+
+use crate::{
+    color::{color_name, parse_color},
+    data::{Canvas, Layer, Plane, ATTRIBUTES, MAX_SIZE},
+    generate_colors, import,
+};
+
+const MAGIC: &str = "termdraw";
+const VERSION: u32 = 1;
+
+pub fn save(canvas: &Canvas, path: &Path) -> io::Result<()> {
+    fs::write(path, serialize(canvas))
+}
+
+pub fn load(path: &Path) -> io::Result<Canvas> {
+    deserialize(&fs::read_to_string(path)?)
+}
+
+/// Loads a drawing, or imports it if it's in another format
+pub fn open(path: &Path) -> io::Result<Canvas> {
+    if import::can_import(path) {
+        import::import(path)
+    } else {
+        load(path)
+    }
+}
+
+pub fn serialize(canvas: &Canvas) -> String {
+    let (width, height) = canvas.size();
+    let mut keys = ColorKeys::new();
+    let mut layers = String::new();
+    for plane in &canvas.planes {
+        let mut text = String::new();
+        let mut colors = String::new();
+        let mut backgrounds = String::new();
+        let mut attributes = String::new();
+        for row in 0..height {
+            for col in 0..width {
+                match plane.cells[col][row] {
+                    Some(cell) => {
+                        // Control characters would break the line structure
+                        text.push(if cell.brush.is_control() {
+                            ' '
+                        } else {
+                            cell.brush
+                        });
+                        colors.push(keys.key(cell.brush_color));
+                        backgrounds.push(match cell.bg_color {
+                            Color::Reset => ' ',
+                            color => keys.key(color),
+                        });
+                        attributes.push(attribute_key(cell.attributes));
+                    }
+                    None => {
+                        text.push(' ');
+                        colors.push(' ');
+                        backgrounds.push(' ');
+                        attributes.push(' ');
+                    }
+                }
+            }
+            text.push('\n');
+            colors.push('\n');
+            backgrounds.push('\n');
+            attributes.push('\n');
+        }
+        layers += &format!("layer {}\n", plane.name);
+        if !plane.visible {
+            layers += "hidden\n";
+        }
+        if plane.locked {
+            layers += "locked\n";
+        }
+        layers += "text\n";
+        layers += &text;
+        layers += "color\n";
+        layers += &colors;
+        // Most drawings don't use these, so they're left out unless needed
+        if backgrounds.chars().any(|key| !key.is_whitespace()) {
+            layers += "background\n";
+            layers += &backgrounds;
+        }
+        if attributes.chars().any(|key| !key.is_whitespace()) {
+            layers += "attributes\n";
+            layers += &attributes;
+        }
+    }
+
+    let mut out = format!("{} {}\nsize {} {}\ncolors\n", MAGIC, VERSION, width, height);
+    for (key, color) in keys.used() {
+        out += &format!("{} {}\n", key, color_name(color));
+    }
+    out + &layers
+}
+
+pub fn deserialize(input: &str) -> io::Result<Canvas> {
+    let mut lines = input.lines().peekable();
+
+    let header = lines.next().unwrap_or_default();
+    match header.split_once(' ') {
+        Some((MAGIC, version)) => {
+            let version: u32 = version.trim().parse().map_err(|_| invalid("bad version"))?;
+            if version != VERSION {
+                return Err(invalid(&format!("unsupported version {}", version)));
+            }
+        }
+        _ => return Err(invalid("not a termdraw file")),
+    }
+
+    let (width, height) = lines
+        .next()
+        .and_then(|line| line.strip_prefix("size "))
+        .and_then(|size| size.split_once(' '))
+        .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)))
+        .ok_or_else(|| invalid("missing size"))?;
+    if width > MAX_SIZE || height > MAX_SIZE {
+        return Err(invalid(&format!("size {}x{} is too large", width, height)));
+    }
+
+    if lines.next() != Some("colors") {
+        return Err(invalid("missing colors"));
+    }
+    let mut palette = HashMap::new();
+    while let Some(line) = lines.next_if(|&line| !line.starts_with("layer ")) {
+        let mut chars = line.chars();
+        let key = chars.next().ok_or_else(|| invalid("empty color entry"))?;
+        let color = parse_color(chars.as_str().trim())
+            .ok_or_else(|| invalid(&format!("bad color {:?}", line)))?;
+        palette.insert(key, color);
+    }
+
+    let mut canvas = Canvas::new(width, height);
+    let (width, height) = (width as usize, height as usize);
+    canvas.planes.clear();
+    while let Some(line) = lines.next() {
+        let name = line
+            .strip_prefix("layer ")
+            .ok_or_else(|| invalid(&format!("expected a layer, got {:?}", line)))?;
+        let mut plane = Plane::new(canvas.planes.len(), name, width, height);
+        while let Some(flag) = lines.next_if(|&line| line != "text") {
+            match flag {
+                "hidden" => plane.visible = false,
+                "locked" => plane.locked = true,
+                _ => return Err(invalid(&format!("unknown layer flag {:?}", flag))),
+            }
+        }
+        lines.next();
+        read_grids(&mut lines, &mut plane, height, &palette)?;
+        canvas.planes.push(plane);
+    }
+    if canvas.planes.is_empty() {
+        return Err(invalid("no layers"));
+    }
+    canvas.reset_plane_ids();
+    canvas.composite_all();
+    Ok(canvas)
+}
+
+/// Reads the text rows, the `color` line and the color rows into `plane`, followed by
+/// the optional `background` and `attributes` grids. Cells without a color key are
+/// transparent.
+fn read_grids(
+    lines: &mut Peekable<Lines>,
+    plane: &mut Plane,
+    height: usize,
+    palette: &HashMap<char, Color>,
+) -> io::Result<()> {
+    let width = plane.cells.len();
+    let text: Vec<&str> = lines.by_ref().take(height).collect();
+    if lines.next() != Some("color") {
+        return Err(invalid("missing color grid"));
+    }
+    let colors: Vec<&str> = lines.by_ref().take(height).collect();
+    let mut optional_grid = |name: &str| match lines.next_if_eq(&name) {
+        Some(_) => lines.by_ref().take(height).collect(),
+        None => vec![],
+    };
+    let backgrounds = optional_grid("background");
+    let attributes = optional_grid("attributes");
+
+    // Editors like to strip trailing whitespace, so lines can be short. Missing text is
+    // spaces, and the key grids decide which of those cells are opaque.
+    fn keys<'a>(grid: &[&'a str], row: usize) -> impl Iterator<Item = Option<char>> + 'a {
+        grid.get(row)
+            .map(|line| line.chars())
+            .into_iter()
+            .flatten()
+            .map(|key| (key != ' ').then_some(key))
+            .chain(std::iter::repeat(None))
+    }
+    for (row, text) in text.iter().enumerate() {
+        let cells = text
+            .chars()
+            .chain(std::iter::repeat(' '))
+            .zip(keys(&colors, row))
+            .zip(keys(&backgrounds, row).zip(keys(&attributes, row)));
+        for (col, ((brush, color_key), (bg_key, attribute_key))) in cells.take(width).enumerate() {
+            let color = color_key.and_then(|key| palette.get(&key).copied());
+            plane.cells[col][row] = color.map(|brush_color| Layer {
+                brush,
+                brush_color,
+                bg_color: bg_key
+                    .and_then(|key| palette.get(&key).copied())
+                    .unwrap_or(Color::Reset),
+                attributes: attribute_key.map(parse_attributes).unwrap_or_default(),
+                changed: true,
+            });
+        }
+    }
+    Ok(())
+}
+
+// Attributes are stored as a bit set over ATTRIBUTES, written as a base 32 digit
+const ATTRIBUTE_KEYS: &str = "0123456789abcdefghijklmnopqrstuv";
+
+fn attribute_key(attributes: Attributes) -> char {
+    let bits = ATTRIBUTES
+        .iter()
+        .enumerate()
+        .filter(|(_, (attribute, _))| attributes.has(*attribute))
+        .fold(0, |bits, (bit, _)| bits | 1 << bit);
+    match bits {
+        0 => ' ',
+        bits => ATTRIBUTE_KEYS.chars().nth(bits).unwrap_or(' '),
+    }
+}
+
+fn parse_attributes(key: char) -> Attributes {
+    let bits = ATTRIBUTE_KEYS.find(key).unwrap_or(0);
+    let mut attributes = Attributes::default();
+    for (bit, (attribute, _)) in ATTRIBUTES.iter().enumerate() {
+        if bits & 1 << bit != 0 {
+            attributes.set(*attribute);
+        }
+    }
+    attributes
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// Hands out a single character key per color
+struct ColorKeys {
+    assigned: Vec<(char, Color)>,
+    used: Vec<bool>,
+    free: Box<dyn Iterator<Item = char>>,
+}
+
+impl ColorKeys {
+    fn new() -> Self {
+        let assigned: Vec<(char, Color)> =
+            "0123456789abcdef".chars().zip(generate_colors()).collect();
+        let free = ('g'..='z')
+            .chain('A'..='Z')
+            .chain(('!'..='~').filter(|c| !c.is_ascii_alphanumeric()))
+            .chain('\u{c0}'..);
+        ColorKeys {
+            used: vec![false; assigned.len()],
+            assigned,
+            free: Box::new(free),
+        }
+    }
+
+    fn key(&mut self, color: Color) -> char {
+        let index = match self.assigned.iter().position(|(_, c)| *c == color) {
+            Some(index) => index,
+            None => {
+                let key = self.free.next().expect("ran out of color keys");
+                self.assigned.push((key, color));
+                self.used.push(false);
+                self.assigned.len() - 1
+            }
+        };
+        self.used[index] = true;
+        self.assigned[index].0
+    }
+
+    fn used(&self) -> impl Iterator<Item = (char, Color)> + '_ {
+        self.assigned
+            .iter()
+            .zip(self.used.iter())
+            .filter(|(_, used)| **used)
+            .map(|(assigned, _)| *assigned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Attribute;
+
+    use super::*;
+
+    fn layer(brush: char, brush_color: Color) -> Layer {
+        Layer {
+            brush,
+            brush_color,
+            ..Default::default()
+        }
+    }
+
+    fn assert_same(a: &Canvas, b: &Canvas) {
+        assert_eq!(a.size(), b.size());
+        assert_eq!(a.planes.len(), b.planes.len());
+        for (a, b) in a.planes.iter().zip(&b.planes) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.visible, b.visible);
+            assert_eq!(a.locked, b.locked);
+            for (col, (a, b)) in a.cells.iter().zip(&b.cells).enumerate() {
+                for (row, (a, b)) in a.iter().zip(b).enumerate() {
+                    let same = match (a, b) {
+                        (Some(a), Some(b)) => a.looks_like(b),
+                        (a, b) => a.is_none() && b.is_none(),
+                    };
+                    assert!(same, "{:?} != {:?} at {},{}", a, b, col, row);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut canvas = Canvas::new(4, 2);
+        canvas.set(0, 0, layer('a', Color::Red));
+        let mut styled = layer(' ', Color::Rgb { r: 1, g: 2, b: 3 });
+        styled.bg_color = Color::Blue;
+        styled.attributes.set(Attribute::Bold);
+        styled.attributes.set(Attribute::Reverse);
+        canvas.set(3, 1, styled);
+        canvas.add_plane();
+        canvas.set(1, 0, layer('\u{2588}', Color::AnsiValue(200)));
+        canvas.rename_plane("top");
+        canvas.toggle_locked();
+        canvas.toggle_visible();
+
+        let text = serialize(&canvas);
+        let loaded = deserialize(&text).unwrap();
+        assert_same(&canvas, &loaded);
+        assert_eq!(serialize(&loaded), text);
+    }
+
+    #[test]
+    fn stripped_trailing_spaces() {
+        let input = "termdraw 1\nsize 4 1\ncolors\n0 white\n4 red\nlayer l\n\
+                     text\nab\ncolor\n0000\nbackground\n  44\n";
+        let canvas = deserialize(input).unwrap();
+        for col in 2..4 {
+            let cell = canvas.planes[0].cells[col][0].expect("opaque space");
+            assert_eq!(cell.brush, ' ');
+            assert_eq!(cell.bg_color, Color::Red);
+        }
+    }
+
+    #[test]
+    fn bad_input() {
+        assert!(deserialize("").is_err());
+        assert!(deserialize("something else\n").is_err());
+        assert!(deserialize(&format!("termdraw {}\n", VERSION + 1)).is_err());
+        assert!(deserialize("termdraw 1\nsize 2\ncolors\n").is_err());
+        assert!(deserialize("termdraw 1\nsize 65535 65535\ncolors\n").is_err());
+        assert!(deserialize("termdraw 1\nsize 1 1\ncolors\n").is_err());
+        assert!(deserialize("termdraw 1\nsize 1 1\ncolors\nlayer a\nfoo\n").is_err());
+    }
+}
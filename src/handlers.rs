@@ -1,4 +1,4 @@
-use crossterm::event::{Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
 
 pub fn handle_keyboard<F>(event: &Event, mut f: F)
 where
@@ -9,19 +9,16 @@ where
     }
 }
 
-pub fn handle_keycode<F>(event: &Event, mut f: F)
-where
-    F: FnMut(KeyCode),
-{
-    handle_keyboard(event, |ev| f(ev.code));
-}
-
 pub fn handle_keychar<F>(event: &Event, mut f: F)
 where
     F: FnMut(char),
 {
-    handle_keycode(event, |code| {
-        if let KeyCode::Char(c) = code {
+    handle_keyboard(event, |ev| {
+        // CTRL+<char> is a shortcut, not a character
+        if ev.modifiers.contains(KeyModifiers::CONTROL) {
+            return;
+        }
+        if let KeyCode::Char(c) = ev.code {
             f(c);
         }
     })
@@ -48,6 +45,17 @@ where
     });
 }
 
+/// Whether the event is part of a mouse stroke that's still in progress
+pub fn is_stroke(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(_) | MouseEventKind::Drag(_),
+            ..
+        })
+    )
+}
+
 pub fn get_click_pos(event: &Event) -> Option<(u8, u8)> {
     let mut v = None;
     handle_mouse(event, |ev| {
@@ -0,0 +1,79 @@
+use crate::data::{Layer, Plane};
+
+// How many undo steps we keep around before dropping the oldest ones
+const HISTORY_LIMIT: usize = 256;
+
+/// A single cell of a plane going from `before` to `after`
+#[derive(Copy, Clone)]
+pub struct Edit {
+    /// Id of the plane, which stays the same when planes get reordered
+    pub plane: usize,
+    pub col: usize,
+    pub row: usize,
+    pub before: Option<Layer>,
+    pub after: Option<Layer>,
+}
+
+/// What a single undo step takes back
+#[derive(Clone)]
+pub enum Step {
+    Cells(Vec<Edit>),
+    /// A plane that was deleted, and where it was in the stack
+    RemovePlane {
+        index: usize,
+        plane: Plane,
+    },
+}
+
+/// Undo/redo stacks. Every change to the canvas gets recorded into `pending`,
+/// which is turned into a single undo step by `commit`. That way a whole brush
+/// drag is undone at once instead of one cell at a time.
+#[derive(Default)]
+pub struct History {
+    pending: Vec<Edit>,
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+}
+
+impl History {
+    pub fn record(&mut self, edit: Edit) {
+        self.pending.push(edit);
+    }
+
+    pub fn commit(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let edits = std::mem::take(&mut self.pending);
+        self.push_step(Step::Cells(edits));
+    }
+
+    /// Records a change that isn't made of cell edits as its own undo step
+    pub fn push(&mut self, step: Step) {
+        self.commit();
+        self.push_step(step);
+    }
+
+    fn push_step(&mut self, step: Step) {
+        self.undo.push(step);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        // Any new change invalidates whatever we could have redone
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self) -> Option<&Step> {
+        self.commit();
+        let step = self.undo.pop()?;
+        self.redo.push(step);
+        self.redo.last()
+    }
+
+    pub fn redo(&mut self) -> Option<&Step> {
+        self.commit();
+        let step = self.redo.pop()?;
+        self.undo.push(step);
+        self.undo.last()
+    }
+}
@@ -0,0 +1,432 @@
+use crossterm::style::{Attribute, Attributes, Color};
+
+use crate::{
+    color::{ansi_index, ANSI_COLORS},
+    data::{self, Canvas, Layer},
+};
+
+// Width classic ANSI art wraps at when the file doesn't say otherwise
+const DEFAULT_WIDTH: usize = 80;
+// Upper bound for cursor movement, so a bogus escape can't allocate a gigantic canvas
+const MAX_SIZE: usize = data::MAX_SIZE as usize;
+
+/// Code page 437 characters for bytes 128 to 255
+const CP437_HIGH: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+/// Code page 437 glyphs for the control bytes 0 to 31
+const CP437_LOW: &str = "\0☺☻♥♦♣♠•◘○◙♂♀♪♫☼►◄↕‼¶§▬↨↑↓→←∟↔▲▼";
+
+/// Parses ANSI art (or plain text) in UTF-8 or CP437 into a canvas
+pub fn ansi(input: &[u8]) -> Canvas {
+    let (content, sauce) = strip_sauce(input);
+    // Everything after an EOF character is metadata
+    let content = match content.iter().position(|&b| b == 0x1a) {
+        Some(eof) => &content[..eof],
+        None => content,
+    };
+
+    let (chars, wrap, legacy): (Vec<char>, _, _) = match std::str::from_utf8(content) {
+        // A SAUCE record means art scene conventions, even if it's all ASCII
+        Ok(text) if sauce.is_none() => (text.chars().collect(), None, false),
+        _ => (
+            content.iter().map(|&b| cp437(b)).collect(),
+            Some(sauce.flatten().unwrap_or(DEFAULT_WIDTH)),
+            true,
+        ),
+    };
+
+    let mut parser = Parser {
+        cells: vec![],
+        col: 0,
+        row: 0,
+        saved: (0, 0),
+        color: Color::White,
+        bg_color: Color::Reset,
+        attributes: Attributes::default(),
+        bright: false,
+        bright_bg: false,
+        legacy,
+        wrap,
+    };
+    parser.run(&chars);
+
+    let width = parser.cells.iter().map(|(col, _, _)| col + 1).max();
+    let height = parser.cells.iter().map(|(_, row, _)| row + 1).max();
+    let mut canvas = Canvas::new(
+        width.unwrap_or(0).max(wrap.unwrap_or(0)) as u16,
+        height.unwrap_or(0) as u16,
+    );
+    for (col, row, layer) in parser.cells {
+        canvas.planes[0].cells[col][row] = Some(layer);
+    }
+    canvas.composite_all();
+    canvas
+}
+
+/// Splits off a trailing SAUCE record, returning the content and, if there was a record,
+/// the width it declares
+fn strip_sauce(input: &[u8]) -> (&[u8], Option<Option<usize>>) {
+    if input.len() < 128 {
+        return (input, None);
+    }
+    let record = &input[input.len() - 128..];
+    if &record[..7] != b"SAUCE00" {
+        return (input, None);
+    }
+    let width = u16::from_le_bytes([record[96], record[97]]) as usize;
+    // Only character based files (ANSi, ASCII, ...) keep their width in tinfo1
+    let width = (record[94] == 1 && width > 0).then_some(width);
+    (&input[..input.len() - 128], Some(width))
+}
+
+/// The CP437 byte that shows `c`, `None` if there's no such glyph or the byte for it would
+/// be taken as a control character
+pub fn to_cp437(c: char) -> Option<u8> {
+    match c {
+        ' '..='~' => Some(c as u8),
+        '⌂' => Some(127),
+        _ => match CP437_HIGH.chars().position(|high| high == c) {
+            Some(index) => Some(128 + index as u8),
+            None => {
+                let byte = CP437_LOW.chars().position(|low| low == c)? as u8;
+                (byte > 0 && cp437(byte) == c).then_some(byte)
+            }
+        },
+    }
+}
+
+fn cp437(byte: u8) -> char {
+    match byte {
+        // These keep their meaning as control characters
+        b'\x08' | b'\t' | b'\n' | b'\r' | b'\x1a' | b'\x1b' => byte as char,
+        0..=31 => CP437_LOW.chars().nth(byte as usize).unwrap_or(' '),
+        127 => '⌂',
+        128.. => CP437_HIGH.chars().nth(byte as usize - 128).unwrap_or(' '),
+        _ => byte as char,
+    }
+}
+
+struct Parser {
+    cells: Vec<(usize, usize, Layer)>,
+    col: usize,
+    row: usize,
+    saved: (usize, usize),
+    color: Color,
+    bg_color: Color,
+    attributes: Attributes,
+    /// Bold in CP437 art, which means bright colors rather than a bold font
+    bright: bool,
+    /// Blink in CP437 art, which means bright backgrounds with iCE colors
+    bright_bg: bool,
+    legacy: bool,
+    wrap: Option<usize>,
+}
+
+impl Parser {
+    fn run(&mut self, chars: &[char]) {
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '\x1b' => {
+                    i += 1;
+                    if chars.get(i) != Some(&'[') {
+                        // Not a CSI sequence, skip the one character it consists of
+                        i += 1;
+                        continue;
+                    }
+                    i += 1;
+                    let start = i;
+                    while i < chars.len() && !('\x40'..='\x7e').contains(&chars[i]) {
+                        i += 1;
+                    }
+                    let params: String = chars[start..i.min(chars.len())].iter().collect();
+                    if let Some(&command) = chars.get(i) {
+                        self.csi(&params, command);
+                    }
+                }
+                '\r' => self.col = 0,
+                '\n' => {
+                    // Unix files don't send \r, so treat \n as a whole line break
+                    self.col = 0;
+                    self.row = (self.row + 1).min(MAX_SIZE);
+                }
+                '\t' => self.col = (self.col / 8 + 1) * 8,
+                '\x08' => self.col = self.col.saturating_sub(1),
+                c if c.is_control() => {}
+                c => self.print(c),
+            }
+            i += 1;
+        }
+    }
+
+    fn print(&mut self, brush: char) {
+        if self.wrap.is_some_and(|width| self.col >= width) {
+            self.col = 0;
+            self.row += 1;
+        }
+        if self.col >= MAX_SIZE || self.row >= MAX_SIZE {
+            return;
+        }
+        // Bold and blink turn the dark colors into their bright versions
+        let brighten = |color, bright| match ansi_index(color).map(usize::from) {
+            Some(index) if bright && index < 8 => ANSI_COLORS[index + 8],
+            _ => color,
+        };
+        let brush_color = brighten(self.color, self.bright);
+        let bg_color = brighten(self.bg_color, self.bright_bg);
+        self.cells.push((
+            self.col,
+            self.row,
+            Layer {
+                brush,
+                brush_color,
+                bg_color,
+                attributes: self.attributes,
+                changed: true,
+            },
+        ));
+        self.col += 1;
+    }
+
+    fn csi(&mut self, params: &str, command: char) {
+        let numbers: Vec<usize> = params
+            .trim_start_matches('?')
+            .split(';')
+            .map(|n| n.parse().unwrap_or(0))
+            .collect();
+        let n = numbers.first().copied().unwrap_or(0).max(1);
+        match command {
+            'm' => self.sgr(&numbers),
+            'A' => self.row = self.row.saturating_sub(n),
+            'B' => self.row = self.row.saturating_add(n).min(MAX_SIZE),
+            'C' => self.col = self.col.saturating_add(n).min(MAX_SIZE),
+            'D' => self.col = self.col.saturating_sub(n),
+            'H' | 'f' => {
+                let position = |i: usize| numbers.get(i).copied().unwrap_or(1).clamp(1, MAX_SIZE);
+                self.row = position(0) - 1;
+                self.col = position(1) - 1;
+            }
+            's' => self.saved = (self.col, self.row),
+            'u' => (self.col, self.row) = self.saved,
+            // Clearing, modes etc. don't change what ends up in the drawing
+            _ => {}
+        }
+    }
+
+    fn sgr(&mut self, numbers: &[usize]) {
+        let mut numbers = numbers.iter().copied();
+        while let Some(n) = numbers.next() {
+            match n {
+                0 => {
+                    self.color = Color::White;
+                    self.bg_color = Color::Reset;
+                    self.attributes = Attributes::default();
+                    self.bright = false;
+                    self.bright_bg = false;
+                }
+                1 if self.legacy => self.bright = true,
+                5 if self.legacy => self.bright_bg = true,
+                25 => self.bright_bg = false,
+                1 => self.attributes.set(Attribute::Bold),
+                2 => self.attributes.set(Attribute::Dim),
+                3 => self.attributes.set(Attribute::Italic),
+                4 => self.attributes.set(Attribute::Underlined),
+                7 => self.attributes.set(Attribute::Reverse),
+                22 => {
+                    self.bright = false;
+                    self.attributes.unset(Attribute::Bold);
+                    self.attributes.unset(Attribute::Dim);
+                }
+                23 => self.attributes.unset(Attribute::Italic),
+                24 => self.attributes.unset(Attribute::Underlined),
+                27 => self.attributes.unset(Attribute::Reverse),
+                30..=37 => self.color = ANSI_COLORS[n - 30],
+                39 => self.color = Color::White,
+                40..=47 => self.bg_color = ANSI_COLORS[n - 40],
+                49 => self.bg_color = Color::Reset,
+                90..=97 => self.color = ANSI_COLORS[n - 90 + 8],
+                100..=107 => self.bg_color = ANSI_COLORS[n - 100 + 8],
+                38 | 48 => {
+                    let color = match numbers.next() {
+                        Some(5) => numbers.next().map(|n| match n {
+                            0..=15 => ANSI_COLORS[n],
+                            n => Color::AnsiValue(n as u8),
+                        }),
+                        Some(2) => {
+                            let mut channel = || numbers.next().unwrap_or(0) as u8;
+                            Some(Color::Rgb {
+                                r: channel(),
+                                g: channel(),
+                                b: channel(),
+                            })
+                        }
+                        _ => None,
+                    };
+                    match (n, color) {
+                        (38, Some(color)) => self.color = color,
+                        (48, Some(color)) => self.bg_color = color,
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{self, AnsiOptions};
+
+    fn layer(brush: char, brush_color: Color, bg_color: Color) -> Layer {
+        Layer {
+            brush,
+            brush_color,
+            bg_color,
+            ..Default::default()
+        }
+    }
+
+    fn drawing() -> Canvas {
+        let mut canvas = Canvas::new(6, 3);
+        let mut bold = layer('h', Color::Red, Color::Reset);
+        bold.attributes.set(Attribute::Bold);
+        canvas.set(0, 0, bold);
+        canvas.set(1, 0, Layer { brush: 'i', ..bold });
+        let rgb = |r, g, b| Color::Rgb { r, g, b };
+        canvas.set(3, 0, layer('─', rgb(10, 20, 30), rgb(200, 100, 0)));
+        canvas.set(0, 1, layer(' ', Color::White, Color::Blue));
+        let mut underlined = layer('x', Color::Green, Color::Reset);
+        underlined.attributes.set(Attribute::Underlined);
+        canvas.set(1, 1, underlined);
+        // The color of a reversed space is its background
+        let mut reversed = layer(' ', Color::DarkRed, Color::Reset);
+        reversed.attributes.set(Attribute::Reverse);
+        canvas.set(2, 1, reversed);
+        canvas.set(0, 2, layer('█', Color::AnsiValue(200), Color::Reset));
+        canvas
+    }
+
+    #[test]
+    fn round_trip() {
+        let canvas = drawing();
+        let options = AnsiOptions {
+            truecolor: true,
+            sauce: false,
+        };
+        let imported = ansi(&export::ansi(&canvas, options));
+        for (col, column) in canvas.vd.iter().enumerate() {
+            for (row, cell) in column.iter().enumerate() {
+                let blank = cell.brush == ' '
+                    && cell.bg_color == Color::Reset
+                    && cell.attributes.is_empty();
+                if blank {
+                    continue;
+                }
+                let got = imported.vd[col][row];
+                assert_eq!(got.brush, cell.brush, "at {},{}", col, row);
+                assert_eq!(got.bg_color, cell.bg_color, "at {},{}", col, row);
+                assert_eq!(got.attributes, cell.attributes, "at {},{}", col, row);
+                let fg_shows = cell.brush != ' '
+                    || cell.attributes.has(Attribute::Reverse)
+                    || cell.attributes.has(Attribute::Underlined);
+                if fg_shows {
+                    assert_eq!(got.brush_color, cell.brush_color, "at {},{}", col, row);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sauce_width() {
+        let options = AnsiOptions {
+            truecolor: false,
+            sauce: true,
+        };
+        let imported = ansi(&export::ansi(&drawing(), options));
+        // Without the SAUCE record the trailing blank columns would be cut off
+        assert_eq!(imported.size(), (6, 3));
+        for cell in imported.vd.iter().flatten() {
+            assert!(ansi_index(cell.brush_color).is_some() || cell.brush_color == Color::Reset);
+        }
+    }
+
+    #[test]
+    fn cp437() {
+        // Invalid UTF-8, so it's read as CP437 where bold means bright
+        let imported = ansi(b"\x1b[1;31m\xdb\xc4\x1b[0m\r\n\xc9");
+        let cells: Vec<(char, Color)> = [(0, 0), (1, 0), (0, 1)]
+            .iter()
+            .map(|&(col, row)| {
+                let cell = imported.vd[col][row];
+                (cell.brush, cell.brush_color)
+            })
+            .collect();
+        assert_eq!(
+            cells,
+            [('█', Color::Red), ('─', Color::Red), ('╔', Color::White)]
+        );
+        // Legacy files wrap at 80 columns
+        assert_eq!(imported.size().0, DEFAULT_WIDTH);
+    }
+
+    #[test]
+    fn huge_cursor_moves() {
+        // The cursor stops at the edge instead of overflowing, and whatever is past it is dropped
+        for input in [
+            "a\x1b[18446744073709551615Cb",
+            "a\n\x1b[18446744073709551615Bb",
+        ] {
+            let imported = ansi(input.as_bytes());
+            let (width, height) = imported.size();
+            assert!(width <= MAX_SIZE && height <= MAX_SIZE, "{:?}", input);
+            assert_eq!(imported.vd[0][0].brush, 'a');
+            assert!(imported.vd.iter().flatten().all(|cell| cell.brush != 'b'));
+        }
+    }
+
+    #[test]
+    fn classic_export() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.set(0, 0, layer('─', Color::Red, Color::Reset));
+        canvas.set(1, 0, layer('x', Color::DarkRed, Color::Blue));
+        let options = AnsiOptions {
+            truecolor: false,
+            sauce: true,
+        };
+        let out = export::ansi(&canvas, options);
+        // CP437 text, bold for the bright red and blink for the bright blue background
+        let expected = b"\x1b[1;31m\xc4\x1b[0;5;31;44mx\x1b[0m\r\n\x1aSAUCE00";
+        assert_eq!(&out[..expected.len()], expected);
+    }
+
+    #[test]
+    fn classic_round_trip() {
+        let mut canvas = Canvas::new(4, 1);
+        canvas.set(0, 0, layer('a', Color::Yellow, Color::DarkBlue));
+        canvas.set(1, 0, layer('╬', Color::DarkGreen, Color::Magenta));
+        canvas.set(2, 0, layer('░', Color::White, Color::Reset));
+        canvas.set(3, 0, layer('z', Color::Grey, Color::Reset));
+        let options = AnsiOptions {
+            truecolor: false,
+            sauce: true,
+        };
+        let imported = ansi(&export::ansi(&canvas, options));
+        for (col, column) in canvas.vd.iter().enumerate() {
+            let (cell, got) = (column[0], imported.vd[col][0]);
+            assert_eq!(
+                (got.brush, got.brush_color, got.bg_color),
+                (cell.brush, cell.brush_color, cell.bg_color),
+                "at {}",
+                col
+            );
+            assert!(got.attributes.is_empty(), "at {}", col);
+        }
+        // Even when it's all ASCII, the SAUCE record says bold means bright
+        canvas.set(1, 0, layer('b', Color::Cyan, Color::Reset));
+        canvas.set(2, 0, layer('c', Color::DarkCyan, Color::Reset));
+        let imported = ansi(&export::ansi(&canvas, options));
+        assert_eq!(imported.vd[1][0].brush_color, Color::Cyan);
+        assert_eq!(imported.vd[2][0].brush_color, Color::DarkCyan);
+    }
+}
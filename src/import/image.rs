@@ -0,0 +1,122 @@
+use std::{io, path::Path};
+
+use crossterm::style::Color;
+use image::imageops::FilterType;
+
+use crate::{
+    color::{nearest, to_rgb},
+    data::{Clip, Layer},
+    dither::{dither, Dither},
+    generate_colors,
+    ramp::Ramp,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageColor {
+    /// Everything in the default foreground color
+    Mono,
+    /// The closest of the 16 palette colors
+    Palette,
+    /// The exact color of the pixels
+    True,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImageOptions {
+    pub color: ImageColor,
+    pub dither: Dither,
+}
+
+/// Turns the picture at `path` into characters of `ramp` with matching brightness, as big
+/// as fits into `width` by `height` cells
+pub fn image(
+    path: &Path,
+    width: usize,
+    height: usize,
+    ramp: &Ramp,
+    options: ImageOptions,
+) -> io::Result<Clip> {
+    let picture = image::open(path).map_err(io::Error::other)?.into_rgba8();
+    let (picture_width, picture_height) = picture.dimensions();
+    if picture_width == 0 || picture_height == 0 || width == 0 || height == 0 {
+        return Ok(vec![]);
+    }
+    // Cells are about twice as tall as they are wide, so every cell covers two rows of pixels
+    let scale = f64::min(
+        width as f64 / picture_width as f64,
+        height as f64 / (picture_height as f64 / 2.0),
+    );
+    let columns = ((picture_width as f64 * scale).round() as u32).clamp(1, width as u32);
+    let rows = ((picture_height as f64 * scale / 2.0).round() as u32).clamp(1, height as u32);
+    let scaled = image::imageops::resize(&picture, columns, rows, FilterType::Triangle);
+    let grid = |f: &dyn Fn([u8; 4]) -> [f64; 3]| -> Vec<Vec<[f64; 3]>> {
+        (0..columns)
+            .map(|col| {
+                (0..rows)
+                    .map(|row| f(scaled.get_pixel(col, row).0))
+                    .collect()
+            })
+            .collect()
+    };
+
+    let steps = ramp.steps() as f64;
+    let lumas = grid(&|[r, g, b, _]| {
+        let luma = 0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64;
+        [luma / 255.0; 3]
+    });
+    let brushes = dither(lumas, options.dither, 1.0 / steps, |[luma, ..]| {
+        let level = (luma.clamp(0.0, 1.0) * steps).round();
+        (level as usize, [level / steps; 3])
+    });
+
+    let palette = generate_colors();
+    let colors = match options.color {
+        ImageColor::Palette => {
+            let rgb = grid(&|[r, g, b, _]| [r as f64, g as f64, b as f64]);
+            // The gaps between the palette colors are about this big
+            let indices = dither(rgb, options.dither, 64.0, |[r, g, b]| {
+                let channel = |value: f64| value.clamp(0.0, 255.0).round() as u8;
+                let color = nearest(
+                    Color::Rgb {
+                        r: channel(r),
+                        g: channel(g),
+                        b: channel(b),
+                    },
+                    &palette,
+                );
+                let (r, g, b) = to_rgb(color);
+                let index = palette.iter().position(|&c| c == color).unwrap_or(0);
+                (index, [r as f64, g as f64, b as f64])
+            });
+            Some(indices)
+        }
+        _ => None,
+    };
+
+    let cells = (0..columns as usize)
+        .map(|col| {
+            (0..rows as usize)
+                .map(|row| {
+                    let [r, g, b, a] = scaled.get_pixel(col as u32, row as u32).0;
+                    let brush = ramp.chars[brushes[col][row]];
+                    // Dark enough to not show up at all, so leave whatever is below visible
+                    if a < 128 || brush == ' ' {
+                        return None;
+                    }
+                    let brush_color = match (options.color, &colors) {
+                        (ImageColor::Palette, Some(colors)) => palette[colors[col][row]],
+                        (ImageColor::True, _) => Color::Rgb { r, g, b },
+                        _ => Color::White,
+                    };
+                    Some(Layer {
+                        brush,
+                        brush_color,
+                        changed: true,
+                        ..Layer::default()
+                    })
+                })
+                .collect()
+        })
+        .collect();
+    Ok(cells)
+}
@@ -0,0 +1,22 @@
+mod ansi;
+mod image;
+
+use std::{fs, io, path::Path};
+
+use crate::data::Canvas;
+
+pub use ansi::{ansi, to_cp437};
+pub use image::{image, ImageColor, ImageOptions};
+
+/// Extensions of files that get imported instead of loaded as native drawings
+const EXTENSIONS: [&str; 5] = ["ans", "asc", "txt", "nfo", "diz"];
+
+pub fn can_import(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+pub fn import(path: &Path) -> io::Result<Canvas> {
+    Ok(ansi(&fs::read(path)?))
+}
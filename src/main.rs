@@ -1,49 +1,62 @@
 use std::{
     io::{stdout, Stdout, Write},
+    path::PathBuf,
     time::Duration,
     vec,
 };
 
+use cli::Cli;
 use commands::process_shortcuts;
 use futures::{future::FutureExt, select, StreamExt};
 use futures_timer::Delay;
 
 use crossterm::{
     cursor::{self, position},
-    event::{DisableMouseCapture, EnableMouseCapture, Event, EventStream, KeyCode, KeyModifiers},
+    event::{
+        DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, EventStream, KeyCode, KeyModifiers,
+    },
     execute, queue,
-    style::{Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    style::{
+        Attribute, Attributes, Color, SetAttribute, SetAttributes, SetBackgroundColor,
+        SetForegroundColor,
+    },
     terminal::{self, disable_raw_mode, enable_raw_mode, size, Clear, ClearType},
     Result,
 };
-use handlers::{get_click_pos, handle_click};
+use handlers::{get_click_pos, handle_click, is_stroke};
 
 use crate::data::*;
-use crate::modes::Mode;
+use crate::export::Format;
+use crate::modes::{BrushData, ContentBrushData, Mode};
+use crate::ramp::Ramp;
 
+mod cli;
+mod clipboard;
+mod color;
 mod commands;
 mod data;
+mod dither;
+mod export;
+mod file;
 mod handlers;
+mod history;
+mod import;
 mod modes;
-
-const LUMA_VALUES: [char; 92] = [
-    ' ', '`', '.', '-', '\'', ':', '_', ',', '^', '=', ';', '>', '<', '+', '!', 'r', 'c', '*', '/',
-    'z', '?', 's', 'L', 'T', 'v', ')', 'J', '7', '(', '|', 'F', 'i', '{', 'C', '}', 'f', 'I', '3',
-    '1', 't', 'l', 'u', '[', 'n', 'e', 'o', 'Z', '5', 'Y', 'x', 'j', 'y', 'a', ']', '2', 'E', 'S',
-    'w', 'q', 'k', 'P', '6', 'h', '9', 'd', '4', 'V', 'p', 'O', 'G', 'b', 'U', 'A', 'K', 'X', 'H',
-    'm', '8', 'R', 'D', '#', '$', 'B', 'g', '0', 'M', 'N', 'W', 'Q', '%', '&', '@',
-];
+mod ramp;
+mod shapes;
+mod transform;
 
 fn draw(event: Event, stdout: &mut Stdout, state: &mut State) -> bool {
     // Handle qutting
     if event == Event::Key(KeyCode::Esc.into()) {
-        match state.mode {
-            Mode::Command => {
-                return false;
-            }
-            _ => {}
+        if let Mode::Command = state.mode {
+            return false;
         }
         queue!(stdout, cursor::Hide).unwrap();
+        // Drops the shape of a tool that was still being dragged
+        state.virtual_display.clear_preview();
+        state.virtual_display.set_selection(None);
         state.mode = Mode::Command;
         state.command = Command::Enter(state.mode.clone());
     }
@@ -54,14 +67,20 @@ fn draw(event: Event, stdout: &mut Stdout, state: &mut State) -> bool {
     // Skip gets used to skip processing of an event if it's already been processed
     let mut skip = false;
 
-    // TODO: process resizing
-    // match &event {
-    //     Event::Resize(new_width, new_height) => {}
-    //     _ => {}
-    // }
+    if let Event::Resize(new_width, new_height) = event {
+        state.virtual_display.resize_viewport(new_width, new_height);
+    }
+
+    // Clicks on the status bar are for the UI, not the canvas
+    if let Some((_, row)) = get_click_pos(&event) {
+        skip = row as u16 + 1 == max_height;
+    }
+
+    // Modes work in canvas coordinates, which differ from the terminal's once it's scrolled
+    let canvas_event = state.virtual_display.translate(&event);
 
     // TODO: custom colors
-    handle_click(&event, |ev, col, row| {
+    handle_click(&canvas_event, |ev, col, row| {
         // Color palette
         // if row + 1 == max_height {
         //     let offset = format!(" {} ", state.mode).len() as u16 + 1;
@@ -81,26 +100,56 @@ fn draw(event: Event, stdout: &mut Stdout, state: &mut State) -> bool {
     queue!(stdout, SetForegroundColor(state.color)).unwrap();
     // Process the event onto the virtual display
     if !skip {
-        state.run(&event, stdout);
+        state.run(&canvas_event, stdout);
+    } else {
+        // Drags that follow belong to a stroke the modes never saw start
+        state.drag_pos = None;
+    }
+
+    // Everything drawn until the mouse button is released counts as one undo step
+    if !is_stroke(&event) {
+        state.virtual_display.history.commit();
+        state.drag_pos = None;
     }
 
     // process shortcuts
     process_shortcuts(&event, stdout, state);
 
     // Draw all changes on the canvas if they need changes
+    if state.virtual_display.need_clear {
+        queue!(stdout, Clear(ClearType::All)).unwrap();
+        state.virtual_display.need_clear = false;
+    }
     if state.virtual_display.need_repaint {
         state.repaint_counter += 1;
         // We loop over everything instead of using some sort of changed cache because that sounds complicated and we're not looping much. Computers are fast.
-        for (col_pos, column) in state.virtual_display.vd.iter_mut().enumerate() {
-            for (row_pos, element) in column.iter_mut().enumerate() {
+        let (view_width, view_height) = state.virtual_display.viewport;
+        let (offset_x, offset_y) = state.virtual_display.offset;
+        let preview = &state.virtual_display.preview;
+        let selection = state.virtual_display.selection;
+        let columns = state.virtual_display.vd.iter_mut().enumerate();
+        for (col_pos, column) in columns.skip(offset_x.into()).take(view_width.into()) {
+            // Cells outside of the terminal keep their changed flag until they're visible again
+            let rows = column.iter_mut().enumerate();
+            for (row_pos, element) in rows.skip(offset_y.into()).take(view_height.into()) {
                 if !element.changed {
                     continue;
                 }
+                let mut shown = *preview.get(&(col_pos, row_pos)).unwrap_or(element);
+                if selection.is_some_and(|area| area.contains(col_pos as u16, row_pos as u16)) {
+                    shown.attributes.toggle(Attribute::Reverse);
+                }
                 queue!(
                     stdout,
-                    cursor::MoveTo(col_pos as u16, row_pos as u16),
-                    SetForegroundColor(element.brush_color),
-                    crossterm::style::Print(element.brush)
+                    cursor::MoveTo(
+                        (col_pos - offset_x as usize) as u16,
+                        (row_pos - offset_y as usize) as u16
+                    ),
+                    SetAttribute(Attribute::Reset),
+                    SetAttributes(shown.attributes),
+                    SetForegroundColor(shown.brush_color),
+                    SetBackgroundColor(shown.bg_color),
+                    crossterm::style::Print(shown.brush)
                 )
                 .unwrap();
                 element.changed = false;
@@ -122,10 +171,10 @@ fn draw(event: Event, stdout: &mut Stdout, state: &mut State) -> bool {
 
     let mut ui = UI {
         elements: vec![],
-        stdout: stdout,
+        stdout,
         pos: get_click_pos(&event),
         max: (max_width as usize, max_height as usize),
-        bg_color: bar_color.clone(),
+        bg_color: bar_color,
         pad: state.pad,
         // state: state,
         offset: 0,
@@ -138,13 +187,140 @@ fn draw(event: Event, stdout: &mut Stdout, state: &mut State) -> bool {
 
     ui.push(Widget::new(&state.mode, Color::Black));
 
+    // Shows both colors, clicking it swaps them
+    let mut colors = Widget::new("FG/BG", state.color);
+    colors.bg = Some(state.bg_color);
+    if ui.push(colors).clicked() {
+        std::mem::swap(&mut state.color, &mut state.bg_color);
+    }
+
+    if ui
+        .push(Widget::new(state.target.to_string(), Color::White))
+        .clicked()
+    {
+        state.target = state.target.next();
+    }
+
+    if !state.attributes.is_empty() {
+        let attributes: String = ATTRIBUTES
+            .iter()
+            .filter(|(attribute, _)| state.attributes.has(*attribute))
+            .map(|(_, key)| key)
+            .collect();
+        ui.push(Widget::new(attributes, Color::White));
+    }
+
+    if let Mode::Prompt(data) = &state.mode {
+        ui.push(Widget::new(format!(":{}", data.input), Color::White));
+    }
+
+    if let Mode::Rect(data) = &state.mode {
+        let fill = match data.fill {
+            Some(fill) => format!("FILL {}", fill),
+            None => "NO FILL".to_string(),
+        };
+        ui.push(Widget::new(
+            format!("{} {}", data.style, fill),
+            Color::White,
+        ));
+    }
+
+    if let Mode::Fill(data) = &state.mode {
+        let text = format!("{} {}", data.brush, data.matching);
+        ui.push(Widget::new(text, Color::White));
+    }
+
+    if let Mode::Select(data) = &state.mode {
+        if let Some(area) = state.virtual_display.selection {
+            let (width, height) = area.size();
+            ui.push(Widget::new(format!("{}x{}", width, height), Color::White));
+        }
+        if data.floating.is_some() {
+            ui.push(Widget::new("FLOATING", Color::White));
+        }
+    }
+
+    if let Mode::HalfBlock(data) = &state.mode {
+        ui.push(Widget::new(
+            if data.erase { "ERASE" } else { "PAINT" },
+            Color::White,
+        ));
+        ui.push(Widget::new(
+            if data.bottom { "BOTTOM" } else { "TOP" },
+            Color::White,
+        ));
+    }
+
+    if let Mode::Braille(data) = &state.mode {
+        ui.push(Widget::new(
+            if data.line { "LINE" } else { "FREEHAND" },
+            Color::White,
+        ));
+    }
+
+    // Tools with their own ramp show it, the others get the one of the session
+    let ramp = match &state.mode {
+        Mode::Brush(BrushData {
+            ramp: Some(ramp), ..
+        })
+        | Mode::ContentBrush(ContentBrushData { ramp: Some(ramp) }) => Some(ramp),
+        Mode::Brush(_) | Mode::ContentBrush(_) => Some(&state.ramp),
+        _ => None,
+    };
+    if let Some(ramp) = ramp {
+        ui.push(Widget::new(ramp.name.to_uppercase(), Color::White));
+    }
+
+    if let Mode::Layers = state.mode {
+        // The whole stack, top first, so it reads like the layers it describes
+        let canvas = &mut state.virtual_display;
+        for index in (0..canvas.planes.len()).rev() {
+            let plane = &canvas.planes[index];
+            let mut widget = Widget::new(
+                format!("{}{}", if plane.locked { "#" } else { "" }, plane.name),
+                if plane.visible {
+                    Color::White
+                } else {
+                    Color::DarkGrey
+                },
+            );
+            if index == canvas.active {
+                widget.bg = Some(Color::DarkBlue);
+            }
+            if ui.push(widget).clicked() {
+                canvas.active = index;
+            }
+        }
+    } else {
+        let canvas = &state.virtual_display;
+        ui.push(Widget::new(
+            &canvas.planes[canvas.active].name,
+            Color::White,
+        ));
+    }
+
     ui.push(Widget::new("T", state.color));
 
     if ui.push(Widget::new("RED", state.color)).clicked() {
         state.color = Color::Red;
     }
 
-    state.pad = ui.render((max_width.into(), max_height.into()), bar_color);
+    let export = Widget::new(format!("EXPORT {}", state.export_format), Color::White);
+    if ui.push(export).clicked() {
+        state.export_format = state.export_format.next();
+    }
+
+    let (offset_x, offset_y) = state.virtual_display.offset;
+    if (offset_x, offset_y) != (0, 0) {
+        ui.push(Widget::new(
+            format!("+{},{}", offset_x, offset_y),
+            Color::White,
+        ));
+    }
+
+    ui.push(Widget::new(state.command.to_string(), Color::White));
+
+    state.pad = ui.render((max_width.into(), max_height.into()));
 
     queue!(
         stdout,
@@ -158,7 +334,7 @@ fn draw(event: Event, stdout: &mut Stdout, state: &mut State) -> bool {
     true
 }
 
-async fn event_handler() {
+async fn event_handler(cli: Cli) {
     let mut reader = EventStream::new();
     // let mut brush_color = Color::White;
     let termsize = terminal::size().unwrap_or_default();
@@ -167,14 +343,39 @@ async fn event_handler() {
         repaint_counter: 0,
         mode: Mode::Command,
         color: Color::White,
+        bg_color: Color::Reset,
+        attributes: Attributes::default(),
+        target: PaintTarget::Fg,
         pos: (0, 0),
         command: Command::None,
-        colors: generate_colors(),
         pad: 0,
-        drag_pos: (0, 0),
+        drag_pos: None,
         // ui: UI { elements: vec![] },
         virtual_display: Canvas::new(termsize.0, termsize.1),
+        path: PathBuf::from("drawing.termdraw"),
+        export_format: Format::Text,
+        clipboard: vec![],
+        ramp: Ramp::from_env(),
+        discard: None,
     };
+    match cli {
+        // A file that doesn't exist yet is where the new drawing gets saved
+        Cli::Edit(Some(path)) if path.exists() => state.command = state.open(path),
+        Cli::Edit(Some(path)) => state.path = path,
+        Cli::New {
+            width,
+            height,
+            path,
+        } => {
+            let mut canvas = Canvas::new(width.unwrap_or(termsize.0), height.unwrap_or(termsize.1));
+            canvas.resize_viewport(termsize.0, termsize.1);
+            state.virtual_display = canvas;
+            if let Some(path) = path {
+                state.path = path;
+            }
+        }
+        _ => {}
+    }
 
     let mut stdoout_temp = stdout();
     draw(
@@ -193,7 +394,7 @@ async fn event_handler() {
             maybe_event = event => {
                 match maybe_event {
                     Some(Ok(event)) => {
-                        if let false = draw(event, &mut stdout, &mut state) {
+                        if !draw(event, &mut stdout, &mut state) {
                             break;
                         }
                     }
@@ -230,6 +431,35 @@ fn generate_colors() -> Vec<Color> {
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprint!("termdraw: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    // These don't need the terminal
+    let done = match &cli {
+        Cli::Help => {
+            print!("{}", cli::USAGE);
+            Some(Ok(()))
+        }
+        Cli::Export {
+            input,
+            format,
+            output,
+        } => Some(cli::export(input, *format, output.as_deref())),
+        Cli::View(path) => Some(cli::view(path)),
+        Cli::Edit(_) | Cli::New { .. } => None,
+    };
+    if let Some(done) = done {
+        if let Err(e) = done {
+            eprintln!("termdraw: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     enable_raw_mode()?;
 
     let mut stdout = stdout();
@@ -237,14 +467,15 @@ async fn main() -> Result<()> {
         stdout,
         Clear(ClearType::All),
         EnableMouseCapture,
+        EnableBracketedPaste,
         cursor::EnableBlinking,
         cursor::SetCursorShape(cursor::CursorShape::Line),
         cursor::Hide
     )?;
 
-    event_handler().await;
+    event_handler(cli).await;
 
-    execute!(stdout, DisableMouseCapture)?;
+    execute!(stdout, DisableMouseCapture, DisableBracketedPaste)?;
 
     disable_raw_mode()
 }
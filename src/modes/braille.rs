@@ -0,0 +1,75 @@
+use crate::{
+    data::*,
+    handlers::{handle_click, handle_keyboard},
+    shapes,
+};
+use crossterm::event::{Event, KeyCode, MouseButton, MouseEventKind};
+
+use super::Mode;
+
+pub fn braille(event: &Event, state: &mut State) {
+    let Mode::Braille(mut data) = state.mode.clone() else {
+        unreachable!()
+    };
+    handle_keyboard(event, |key| {
+        if key.code == KeyCode::Tab {
+            data.line = !data.line;
+        }
+    });
+    if data.line {
+        super::drag_shape(event, state, &mut data.anchor, |from, to| {
+            shapes::braille(&shapes::line(dot(from), dot(to)))
+        });
+    } else {
+        handle_click(event, |ev, col, row| {
+            let (MouseEventKind::Down(button) | MouseEventKind::Drag(button)) = ev.kind else {
+                return;
+            };
+            let to = dot((col, row));
+            let from = match (ev.kind, state.drag_pos) {
+                (MouseEventKind::Down(_), _) => to,
+                (_, Some(pos)) => pos,
+                (_, None) => return,
+            };
+            let cells = shapes::braille(&shapes::line(from, to));
+            if button == MouseButton::Left {
+                for (col, row, brush) in cells {
+                    let old = state
+                        .virtual_display
+                        .cell(col, row)
+                        .map_or(' ', |cell| cell.brush);
+                    state.stroke(col, row, shapes::merge(old, brush));
+                }
+            } else {
+                erase(state, cells);
+            }
+            state.drag_pos = Some(to);
+        });
+    }
+    state.mode = Mode::Braille(data);
+}
+
+// The mouse only knows which cell it's in, so it always points at the same dot of it
+fn dot((col, row): (u16, u16)) -> (u16, u16) {
+    (
+        col.saturating_mul(2),
+        row.saturating_mul(4).saturating_add(1),
+    )
+}
+
+// Takes the dots of `cells` out of the braille already on the active layer
+fn erase(state: &mut State, cells: Vec<(u16, u16, char)>) {
+    for (col, row, brush) in cells {
+        let old = state.virtual_display.cell(col, row).map(|cell| cell.brush);
+        let (Some(old), Some(dots)) = (
+            old.and_then(shapes::braille_dots),
+            shapes::braille_dots(brush),
+        ) else {
+            continue;
+        };
+        match old & !dots {
+            0 => state.virtual_display.erase(col, row),
+            left => state.stroke(col, row, shapes::braille_char(left)),
+        }
+    }
+}
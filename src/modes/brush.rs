@@ -2,13 +2,11 @@ use std::io::Stdout;
 
 use crate::{
     data::*,
+    dither,
     handlers::{handle_click, handle_keychar, handle_mouse},
-    LUMA_VALUES,
-};
-use crossterm::{
-    event::{Event, MouseEventKind},
-    terminal,
+    ramp,
 };
+use crossterm::event::{Event, MouseEventKind};
 
 use std::cmp::min;
 
@@ -18,18 +16,18 @@ pub fn base_brush<F>(state: &mut State, col: u16, row: u16, radius: i32, mut f:
 where
     F: FnMut(&mut State, usize, u16, u16),
 {
-    let (mx, my) = terminal::size().unwrap_or_default();
-    let (mx, my) = (mx.try_into().unwrap(), my.try_into().unwrap());
+    let (mx, my) = state.virtual_display.size();
+    let (mx, my) = (mx as i32, my as i32);
 
-    let col: i32 = col.try_into().unwrap();
-    let row: i32 = row.try_into().unwrap();
+    let col: i32 = col.into();
+    let row: i32 = row.into();
     for x in -radius..=radius {
         for y in -radius..=radius {
             let grr = ((x * x) * 100 / radius / 2) + ((y * y) * 100 / radius);
             if grr <= 100 {
                 let xc: i32 = x + col;
                 let yr: i32 = y + row;
-                if xc > 0 && xc < mx && yr > 0 && yr < my {
+                if xc >= 0 && xc < mx && yr >= 0 && yr < my {
                     f(
                         state,
                         (100 - grr) as usize,
@@ -43,16 +41,18 @@ where
 }
 
 pub fn brush(event: &Event, _stdout: &mut Stdout, state: &mut State) {
-    let (mode, size) = {
+    let (mode, size, ordered, ramp) = {
         let data = match &mut state.mode {
             super::Mode::Brush(t) => t,
             _ => unreachable!(),
         };
-        handle_keychar(&event, |c| match c {
+        handle_keychar(event, |c| match c {
             'a' => data.mode = BrushMode::Add,
             'f' => data.mode = BrushMode::Subtract,
             's' => data.size += 1,
             'd' => data.size = if data.size == 1 { 1 } else { data.size - 1 },
+            't' => data.ordered = !data.ordered,
+            'r' => data.ramp = Some(data.ramp.as_ref().unwrap_or(&state.ramp).next()),
             _ => {}
         });
         handle_mouse(event, |ev| match ev.kind {
@@ -62,7 +62,8 @@ pub fn brush(event: &Event, _stdout: &mut Stdout, state: &mut State) {
             }
             _ => {}
         });
-        (data.mode.clone(), data.size)
+        let ramp = data.ramp.clone().unwrap_or_else(|| state.ramp.clone());
+        (data.mode.clone(), data.size, data.ordered, ramp)
     };
 
     handle_click(event, |_, col, row| {
@@ -70,35 +71,31 @@ pub fn brush(event: &Event, _stdout: &mut Stdout, state: &mut State) {
             state,
             col,
             row,
-            size.try_into().unwrap(),
+            size.into(),
             |state: &mut State, new_luma: usize, col: u16, row: u16| {
                 let old_luma = state
                     .virtual_display
                     .get(col, row)
-                    .and_then(|el| LUMA_VALUES.into_iter().position(|x| x == el.brush))
+                    .and_then(|el| ramp.level(el.brush))
                     .unwrap_or(0);
                 let luma_value = match mode {
                     BrushMode::Add => {
-                        let old_luma = old_luma / 4 + (rand::random::<u8>() / 4) as usize;
-                        LUMA_VALUES[min(old_luma + new_luma, LUMA_VALUES.len() - 1)]
-                    }
-                    BrushMode::Subtract => {
-                        LUMA_VALUES[if old_luma < new_luma {
-                            0
+                        let jitter = if ordered {
+                            (dither::bayer(col.into(), row.into()) * 64.0) as usize
                         } else {
-                            old_luma - new_luma
-                        }]
+                            (rand::random::<u8>() / 4) as usize
+                        };
+                        let old_luma = old_luma / 4 + jitter;
+                        ramp.get(min(old_luma + new_luma, ramp::LEVELS))
                     }
+                    BrushMode::Subtract => ramp.get(old_luma.saturating_sub(new_luma)),
                 };
-                state.virtual_display.set(
-                    col,
-                    row,
-                    Layer {
-                        brush: luma_value,
-                        brush_color: state.color,
-                        changed: true,
-                    },
-                );
+                // Rubbing a cell out completely lets the layers below show through again
+                if luma_value == ramp.chars[0] {
+                    state.virtual_display.erase(col, row);
+                    return;
+                }
+                state.stroke(col, row, luma_value);
             },
         );
     });
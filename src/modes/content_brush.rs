@@ -1,18 +1,26 @@
 use std::io::Stdout;
 
-use crate::{data::*, LUMA_VALUES};
-use crossterm::{
-    event::{Event, MouseButton, MouseEventKind},
-    terminal,
-};
+use crate::{data::*, handlers::handle_keychar};
+use crossterm::event::{Event, MouseButton, MouseEventKind};
 
 pub fn content_brush(event: &Event, _stdout: &mut Stdout, state: &mut State) {
-    match event {
-        Event::Mouse(ev) => match ev.kind {
+    let data = match &mut state.mode {
+        super::Mode::ContentBrush(data) => data,
+        _ => unreachable!(),
+    };
+    handle_keychar(event, |c| {
+        if c == 'r' {
+            data.ramp = Some(data.ramp.as_ref().unwrap_or(&state.ramp).next());
+        }
+    });
+    let ramp = data.ramp.clone().unwrap_or_else(|| state.ramp.clone());
+    if let Event::Mouse(ev) = event {
+        match ev.kind {
             MouseEventKind::Drag(MouseButton::Left) | MouseEventKind::Down(MouseButton::Left) => {
                 let (x, y) = (ev.column, ev.row);
                 let mut average_luma = 0;
-                let (mx, my) = terminal::size().unwrap_or_default();
+                let (mx, my) = state.virtual_display.size();
+                let (mx, my) = (mx as u16, my as u16);
                 let mut divider = 0;
                 let col_range =
                     if x > 1 { x - 1 } else { x }..if x + 1 < mx { x + 1 } else { x } + 1;
@@ -21,31 +29,17 @@ pub fn content_brush(event: &Event, _stdout: &mut Stdout, state: &mut State) {
                 for n in col_range {
                     for i in row_range.clone() {
                         divider += 1;
-                        average_luma += LUMA_VALUES
-                            .iter()
-                            .position(|&val| {
-                                val == state
-                                    .virtual_display
-                                    .get(n, i)
-                                    .and_then(|el| Some(el.brush))
-                                    .unwrap_or('a')
-                            })
+                        average_luma += state
+                            .virtual_display
+                            .get(n, i)
+                            .and_then(|el| ramp.level(el.brush))
                             .unwrap_or(50);
                     }
                 }
-                average_luma = average_luma / divider;
-                state.virtual_display.set(
-                    ev.column,
-                    ev.row,
-                    Layer {
-                        brush: LUMA_VALUES[average_luma],
-                        brush_color: state.color,
-                        changed: true,
-                    },
-                );
+                average_luma /= divider;
+                state.stroke(ev.column, ev.row, ramp.get(average_luma));
             }
             _ => {}
-        },
-        _ => {}
+        }
     }
 }
@@ -0,0 +1,33 @@
+use crate::{data::*, handlers::handle_keyboard, shapes};
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+
+use super::Mode;
+
+pub fn ellipse(event: &Event, state: &mut State) {
+    let Mode::Ellipse(mut data) = state.mode.clone() else {
+        unreachable!()
+    };
+    handle_keyboard(event, |key| match key.code {
+        KeyCode::Tab => data.circle = !data.circle,
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => data.fill = Some(c),
+        KeyCode::Backspace => data.fill = None,
+        _ => {}
+    });
+    let (circle, fill) = (data.circle, data.fill);
+    super::drag_shape(event, state, &mut data.anchor, |from, to| {
+        let to = if circle {
+            // Half as many rows as columns makes it look round
+            let rows = (to.0 as i32 - from.0 as i32).abs() / 2;
+            let row = if to.1 < from.1 {
+                from.1 as i32 - rows
+            } else {
+                from.1 as i32 + rows
+            };
+            (to.0, row.max(0) as u16)
+        } else {
+            to
+        };
+        shapes::ellipse(from, to, fill)
+    });
+    state.mode = Mode::Ellipse(data);
+}
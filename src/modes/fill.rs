@@ -0,0 +1,37 @@
+use crate::{
+    data::*,
+    handlers::{handle_keyboard, handle_mouse},
+    shapes,
+};
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind};
+
+use super::Mode;
+
+pub fn fill(event: &Event, state: &mut State) {
+    let data = match &mut state.mode {
+        Mode::Fill(data) => data,
+        _ => unreachable!(),
+    };
+    handle_keyboard(event, |key| match key.code {
+        KeyCode::Tab => data.matching = data.matching.next(),
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => data.brush = c,
+        _ => {}
+    });
+    let (brush, matching) = (data.brush, data.matching);
+    handle_mouse(event, |ev| {
+        if ev.kind != MouseEventKind::Down(MouseButton::Left) {
+            return;
+        }
+        let canvas = &state.virtual_display;
+        let Some(&start) = canvas.get(ev.column, ev.row) else {
+            return;
+        };
+        let (width, height) = canvas.size();
+        let cells = shapes::flood(width, height, (ev.column, ev.row), |col, row| {
+            matching.matches(&start, &canvas.vd[col][row])
+        });
+        for (col, row) in cells {
+            state.stroke(col, row, brush);
+        }
+    });
+}
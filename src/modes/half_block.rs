@@ -0,0 +1,93 @@
+//! Painting with half-block pixels, two to a cell stacked on top of each other.
+//!
+//! Terminals only report which cell the mouse is in, not where in the cell, so the row
+//! of the mouse can't pick between the two pixels of a cell. The left button paints
+//! the half chosen with Shift+Tab (TOP or BOTTOM in the status bar) and the right
+//! button paints the other one. A drag stays on the half it started on, though slanted
+//! lines step through both halves between the cells they pass.
+
+use crate::{
+    data::*,
+    handlers::{handle_click, handle_keyboard},
+    shapes,
+};
+use crossterm::{
+    event::{Event, KeyCode, MouseButton, MouseEventKind},
+    style::Color,
+};
+
+use super::Mode;
+
+pub fn half_block(event: &Event, state: &mut State) {
+    let data = match &mut state.mode {
+        Mode::HalfBlock(data) => data,
+        _ => unreachable!(),
+    };
+    handle_keyboard(event, |key| match key.code {
+        KeyCode::Tab => data.erase = !data.erase,
+        KeyCode::BackTab => data.bottom = !data.bottom,
+        _ => {}
+    });
+    let color = (!data.erase).then_some(state.color);
+    let bottom = data.bottom;
+    handle_click(event, |ev, col, row| {
+        let (MouseEventKind::Down(button) | MouseEventKind::Drag(button)) = ev.kind else {
+            return;
+        };
+        let half = bottom != (button != MouseButton::Left);
+        // Rows never get anywhere near u16::MAX / 2, the canvas is at most MAX_SIZE high
+        let pixel = (col, row * 2 + half as u16);
+        let from = match (ev.kind, state.drag_pos) {
+            (MouseEventKind::Down(_), _) => pixel,
+            (_, Some(pos)) => pos,
+            (_, None) => return,
+        };
+        for (x, y) in shapes::line(from, pixel) {
+            set_pixel(state, x, y, color);
+        }
+        state.drag_pos = Some(pixel);
+    });
+}
+
+/// Sets the pixel at `x`, `y`, where every cell is two pixels stacked on top of each other
+fn set_pixel(state: &mut State, x: u16, y: u16, color: Option<Color>) {
+    let (col, row) = (x, y / 2);
+    let (mut top, mut bottom) = pixels(state.virtual_display.cell(col, row));
+    if y.is_multiple_of(2) {
+        top = color;
+    } else {
+        bottom = color;
+    }
+    let (brush, brush_color, bg_color) = match (top, bottom) {
+        (None, None) => {
+            state.virtual_display.erase(col, row);
+            return;
+        }
+        (Some(top), Some(bottom)) if top == bottom => ('█', top, Color::Reset),
+        (Some(top), bottom) => ('▀', top, bottom.unwrap_or(Color::Reset)),
+        (None, Some(bottom)) => ('▄', bottom, Color::Reset),
+    };
+    let layer = Layer {
+        brush,
+        brush_color,
+        bg_color,
+        attributes: Default::default(),
+        changed: true,
+    };
+    state.virtual_display.set(col, row, layer);
+}
+
+// The top and bottom pixel of a cell, anything that isn't a half block counts as empty
+fn pixels(cell: Option<Layer>) -> (Option<Color>, Option<Color>) {
+    let Some(cell) = cell else {
+        return (None, None);
+    };
+    let bg = (cell.bg_color != Color::Reset).then_some(cell.bg_color);
+    match cell.brush {
+        '▀' => (Some(cell.brush_color), bg),
+        '▄' => (bg, Some(cell.brush_color)),
+        '█' => (Some(cell.brush_color), Some(cell.brush_color)),
+        ' ' => (bg, bg),
+        _ => (None, None),
+    }
+}
@@ -16,8 +16,6 @@ pub fn hex(event: &Event, state: &mut State) {
                 data.r.0 = Some(val.try_into().unwrap());
             } else if data.r.1.is_none() {
                 data.r.1 = Some(val.try_into().unwrap());
-            } else if data.r.1.is_none() {
-                data.r.1 = Some(val.try_into().unwrap());
             } else if data.g.0.is_none() {
                 data.g.0 = Some(val.try_into().unwrap());
             } else if data.g.1.is_none() {
@@ -41,16 +39,15 @@ pub fn hex(event: &Event, state: &mut State) {
         }
         // not the finest rust ever written
         // TODO make this nice somehow
-        match (data.r, data.g, data.b) {
-            ((Some(r1), Some(r2)), (Some(g1), Some(g2)), (Some(b1), Some(b2))) => {
-                state.color = Color::Rgb {
-                    r: r1 * 16 + r2,
-                    g: g1 * 16 + g2,
-                    b: b1 * 16 + b2,
-                };
-                state.mode = Mode::Brush(BrushData::default())
-            }
-            _ => {}
+        if let ((Some(r1), Some(r2)), (Some(g1), Some(g2)), (Some(b1), Some(b2))) =
+            (data.r, data.g, data.b)
+        {
+            state.color = Color::Rgb {
+                r: r1 * 16 + r2,
+                g: g1 * 16 + g2,
+                b: b1 * 16 + b2,
+            };
+            state.mode = Mode::Brush(BrushData::default())
         }
     });
 }
@@ -3,24 +3,21 @@ use std::io::Stdout;
 use crate::{data::*, handlers::handle_keyboard};
 use crossterm::{
     cursor::{self, position},
-    event::{Event, KeyCode},
+    event::{Event, KeyCode, KeyModifiers},
     execute, queue,
 };
 
 pub fn insert(event: &Event, stdout: &mut Stdout, state: &mut State) {
-    handle_keyboard(&event, |key| {
+    if let Event::Paste(text) = event {
+        paste(text, stdout, state);
+    }
+    handle_keyboard(event, |key| {
         let (col, row) = position().unwrap_or_default();
+        let (offset_x, offset_y) = state.virtual_display.offset;
+        let (col, row) = (col + offset_x, row + offset_y);
         match key.code {
-            KeyCode::Char(code) => {
-                state.virtual_display.set(
-                    col,
-                    row,
-                    Layer {
-                        brush: code,
-                        brush_color: state.color,
-                        changed: true,
-                    },
-                );
+            KeyCode::Char(code) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.stroke(col, row, code);
                 state.pos.0 += 1;
                 execute!(stdout, cursor::MoveRight(1)).unwrap();
             }
@@ -50,3 +47,28 @@ pub fn insert(event: &Event, stdout: &mut Stdout, state: &mut State) {
         };
     });
 }
+
+// Types out pasted text from the cursor on, every line starting in the same column
+fn paste(text: &str, stdout: &mut Stdout, state: &mut State) {
+    let (start, mut row) = position().unwrap_or_default();
+    let (offset_x, offset_y) = state.virtual_display.offset;
+    let mut col = start;
+    for (i, line) in text.lines().enumerate() {
+        if i > 0 {
+            row = row.saturating_add(1);
+        }
+        col = start;
+        for c in line.chars() {
+            let c = if c == '\t' { ' ' } else { c };
+            if !c.is_control() {
+                state.stroke(
+                    col.saturating_add(offset_x),
+                    row.saturating_add(offset_y),
+                    c,
+                );
+            }
+            col = col.saturating_add(1);
+        }
+    }
+    execute!(stdout, cursor::MoveTo(col, row)).unwrap();
+}
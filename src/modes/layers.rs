@@ -0,0 +1,17 @@
+use crate::{data::*, handlers::handle_keychar};
+use crossterm::event::Event;
+
+pub fn layers(event: &Event, state: &mut State) {
+    let canvas = &mut state.virtual_display;
+    handle_keychar(event, |c| match c {
+        'n' => canvas.add_plane(),
+        'x' => canvas.remove_plane(),
+        'k' => canvas.select_plane(1),
+        'j' => canvas.select_plane(-1),
+        'K' => canvas.move_plane(1),
+        'J' => canvas.move_plane(-1),
+        'v' => canvas.toggle_visible(),
+        'l' => canvas.toggle_locked(),
+        _ => {}
+    });
+}
@@ -0,0 +1,24 @@
+use crate::{data::*, handlers::handle_keyboard, shapes};
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+
+use super::Mode;
+
+pub fn line(event: &Event, state: &mut State) {
+    let Mode::Line(mut data) = state.mode.clone() else {
+        unreachable!()
+    };
+    handle_keyboard(event, |key| match key.code {
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => data.brush = Some(c),
+        KeyCode::Backspace => data.brush = None,
+        _ => {}
+    });
+    let brush = data.brush;
+    super::drag_shape(event, state, &mut data.anchor, |from, to| {
+        let brush = brush.unwrap_or_else(|| shapes::line_char(from, to));
+        shapes::line(from, to)
+            .into_iter()
+            .map(|(col, row)| (col, row, brush))
+            .collect()
+    });
+    state.mode = Mode::Line(data);
+}
@@ -1,12 +1,30 @@
+mod braille;
 mod brush;
 mod content_brush;
+mod ellipse;
 mod eyedropper;
+mod fill;
+mod half_block;
 mod hex;
 mod insert;
+mod layers;
+mod line;
 mod pencil;
+mod prompt;
+mod rect;
+mod select;
 
 use std::fmt;
 
+use crossterm::event::{Event, MouseButton, MouseEventKind};
+
+use crate::{
+    data::{Clip, Layer, State},
+    handlers::handle_mouse,
+    ramp::Ramp,
+    shapes::{self, BoxStyle},
+};
+
 #[derive(Debug, Clone)]
 pub enum Mode {
     Brush(BrushData),
@@ -14,8 +32,17 @@ pub enum Mode {
     Insert,
     Command,
     Eyedropper,
-    ContentBrush,
+    ContentBrush(ContentBrushData),
     Hex(HexData),
+    Prompt(PromptData),
+    Layers,
+    Line(LineData),
+    Rect(RectData),
+    Ellipse(EllipseData),
+    Fill(FillData),
+    Select(SelectData),
+    HalfBlock(HalfBlockData),
+    Braille(BrailleData),
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +54,16 @@ pub struct PencilData {
 pub struct BrushData {
     pub size: u8,
     pub mode: BrushMode,
+    /// Shades with a Bayer pattern instead of random noise, so strokes come out the same every time
+    pub ordered: bool,
+    /// Overrides the ramp of the session
+    pub ramp: Option<Ramp>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ContentBrushData {
+    /// Overrides the ramp of the session
+    pub ramp: Option<Ramp>,
 }
 
 impl Default for BrushData {
@@ -34,34 +71,206 @@ impl Default for BrushData {
         Self {
             size: 1,
             mode: BrushMode::Add,
+            ordered: false,
+            ramp: None,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct HexData {
     pub r: (Option<u8>, Option<u8>),
     pub g: (Option<u8>, Option<u8>),
     pub b: (Option<u8>, Option<u8>),
 }
 
-impl Default for HexData {
+#[derive(Debug, Clone, Default)]
+pub struct LineData {
+    /// Where the line being dragged starts
+    pub anchor: Option<(u16, u16)>,
+    /// `None` picks a character matching the slope of the line
+    pub brush: Option<char>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RectData {
+    pub anchor: Option<(u16, u16)>,
+    pub style: BoxStyle,
+    /// What goes inside the box, `None` leaves it alone
+    pub fill: Option<char>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EllipseData {
+    pub anchor: Option<(u16, u16)>,
+    /// Keeps the height at half the width, which looks round in most terminals
+    pub circle: bool,
+    pub fill: Option<char>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FillData {
+    pub brush: char,
+    pub matching: FillMatch,
+}
+
+impl Default for FillData {
     fn default() -> Self {
         Self {
-            r: (None, None),
-            g: (None, None),
-            b: (None, None),
+            brush: '#',
+            matching: FillMatch::Both,
         }
     }
 }
 
+/// What cells need to have in common with the clicked one to get filled
+#[derive(Debug, Clone, Copy)]
+pub enum FillMatch {
+    Char,
+    Color,
+    Both,
+}
+
+impl FillMatch {
+    pub fn next(&self) -> FillMatch {
+        match self {
+            FillMatch::Char => FillMatch::Color,
+            FillMatch::Color => FillMatch::Both,
+            FillMatch::Both => FillMatch::Char,
+        }
+    }
+
+    pub fn matches(&self, a: &Layer, b: &Layer) -> bool {
+        let char = a.brush == b.brush;
+        let color = a.brush_color == b.brush_color && a.bg_color == b.bg_color;
+        match self {
+            FillMatch::Char => char,
+            FillMatch::Color => color,
+            FillMatch::Both => char && color,
+        }
+    }
+}
+
+impl Display for FillMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                FillMatch::Char => "MATCH CHAR",
+                FillMatch::Color => "MATCH COLOR",
+                FillMatch::Both => "MATCH BOTH",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SelectData {
+    /// Where the selection being dragged out starts
+    pub anchor: Option<(u16, u16)>,
+    pub floating: Option<Floating>,
+}
+
+/// Cells that are being moved or pasted, drawn over the canvas until they're dropped
+#[derive(Debug, Clone)]
+pub struct Floating {
+    pub cells: Clip,
+    /// Where the mouse holds the cells, from their top left corner
+    pub grab: (u16, u16),
+    /// Dragged with the button held down, rather than following the mouse after a paste
+    pub moving: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HalfBlockData {
+    /// Clears pixels instead of painting them
+    pub erase: bool,
+    /// The left button paints the bottom half of cells, the right button the top half
+    pub bottom: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BrailleData {
+    /// Drags out straight lines instead of drawing freehand
+    pub line: bool,
+    pub anchor: Option<(u16, u16)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PromptData {
+    pub input: String,
+}
+
 #[derive(Debug, Clone)]
 pub enum BrushMode {
     Add,
     Subtract,
 }
 
+/// Shared by the shape tools: pressing sets the anchor, dragging previews the
+/// shape and releasing draws it. `cells` turns the two corners into the cells to stroke,
+/// lines crossing what's already on the active layer get joined up.
+fn drag_shape<F>(event: &Event, state: &mut State, anchor: &mut Option<(u16, u16)>, cells: F)
+where
+    F: Fn((u16, u16), (u16, u16)) -> Vec<(u16, u16, char)>,
+{
+    handle_mouse(event, |ev| {
+        let pos = (ev.column, ev.row);
+        match (ev.kind, *anchor) {
+            (MouseEventKind::Down(MouseButton::Left), _) => {
+                *anchor = Some(pos);
+                state.preview(&joined(state, cells(pos, pos)));
+            }
+            (MouseEventKind::Drag(MouseButton::Left), Some(from)) => {
+                state.preview(&joined(state, cells(from, pos)));
+            }
+            (MouseEventKind::Up(MouseButton::Left), Some(from)) => {
+                *anchor = None;
+                state.virtual_display.clear_preview();
+                for (col, row, brush) in joined(state, cells(from, pos)) {
+                    state.stroke(col, row, brush);
+                }
+            }
+            _ => {}
+        }
+    });
+}
+
+// Only the active layer counts, a junction with a line on another layer would fall
+// apart as soon as that layer gets hidden or moved
+fn joined(state: &State, cells: Vec<(u16, u16, char)>) -> Vec<(u16, u16, char)> {
+    cells
+        .into_iter()
+        .map(
+            |(col, row, brush)| match state.virtual_display.cell(col, row) {
+                Some(old) => (col, row, shapes::merge(old.brush, brush)),
+                None => (col, row, brush),
+            },
+        )
+        .collect()
+}
+
 impl Mode {
+    /// Whether the mode uses plain keypresses itself, in which case global shortcuts are skipped
+    pub fn reads_keys(&self) -> bool {
+        matches!(
+            self,
+            Mode::Insert
+                | Mode::Pencil(_)
+                | Mode::Brush(_)
+                | Mode::ContentBrush(_)
+                | Mode::Hex(_)
+                | Mode::Prompt(_)
+                | Mode::Layers
+                | Mode::Line(_)
+                | Mode::Rect(_)
+                | Mode::Ellipse(_)
+                | Mode::Fill(_)
+                | Mode::Select(_)
+        )
+    }
+
     pub fn get_color(&self) -> Color {
         match self {
             Mode::Brush(_) => Color::DarkGreen,
@@ -69,8 +278,17 @@ impl Mode {
             Mode::Command => Color::DarkRed,
             Mode::Insert => Color::DarkCyan,
             Mode::Pencil(_) => Color::DarkYellow,
-            Mode::ContentBrush => Color::Green,
+            Mode::ContentBrush(_) => Color::Green,
             Mode::Hex(_) => Color::DarkBlue,
+            Mode::Prompt(_) => Color::DarkGrey,
+            Mode::Layers => Color::Blue,
+            Mode::Line(_) => Color::Magenta,
+            Mode::Rect(_) => Color::Cyan,
+            Mode::Ellipse(_) => Color::Yellow,
+            Mode::Fill(_) => Color::Red,
+            Mode::Select(_) => Color::Grey,
+            Mode::HalfBlock(_) => Color::White,
+            Mode::Braille(_) => Color::DarkMagenta,
         }
     }
 }
@@ -83,8 +301,17 @@ impl From<&Mode> for String {
             Mode::Command => "COMMAND",
             Mode::Insert => "INSERT",
             Mode::Pencil(_) => "PENCIL",
-            Mode::ContentBrush => "CONTENT BRUSH",
+            Mode::ContentBrush(_) => "CONTENT BRUSH",
             Mode::Hex(_) => "HEX",
+            Mode::Prompt(_) => "PROMPT",
+            Mode::Layers => "LAYERS",
+            Mode::Line(_) => "LINE",
+            Mode::Rect(_) => "RECT",
+            Mode::Ellipse(_) => "ELLIPSE",
+            Mode::Fill(_) => "FILL",
+            Mode::Select(_) => "SELECT",
+            Mode::HalfBlock(_) => "HALF BLOCK",
+            Mode::Braille(_) => "BRAILLE",
         }
         .to_owned()
     }
@@ -101,8 +328,17 @@ impl Display for Mode {
                 Mode::Command => "COMMAND",
                 Mode::Insert => "INSERT",
                 Mode::Pencil(_) => "PENCIL",
-                Mode::ContentBrush => "CONTENT BRUSH",
+                Mode::ContentBrush(_) => "CONTENT BRUSH",
                 Mode::Hex(_) => "HEX",
+                Mode::Prompt(_) => "PROMPT",
+                Mode::Layers => "LAYERS",
+                Mode::Line(_) => "LINE",
+                Mode::Rect(_) => "RECT",
+                Mode::Ellipse(_) => "ELLIPSE",
+                Mode::Fill(_) => "FILL",
+                Mode::Select(_) => "SELECT",
+                Mode::HalfBlock(_) => "HALF BLOCK",
+                Mode::Braille(_) => "BRAILLE",
             }
         )
     }
@@ -110,10 +346,19 @@ impl Display for Mode {
 
 use std::fmt::Display;
 
+pub use braille::braille;
 pub use brush::brush;
 pub use content_brush::content_brush;
 use crossterm::style::Color;
+pub use ellipse::ellipse;
 pub use eyedropper::eyedropper;
+pub use fill::fill;
+pub use half_block::half_block;
 pub use hex::hex;
 pub use insert::insert;
+pub use layers::layers;
+pub use line::line;
 pub use pencil::pencil;
+pub use prompt::prompt;
+pub use rect::rect;
+pub use select::select;
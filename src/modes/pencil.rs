@@ -1,26 +1,30 @@
 use crate::{
     data::*,
     handlers::{handle_click, handle_keychar},
+    shapes,
 };
-use crossterm::event::Event;
+use crossterm::event::{Event, MouseEventKind};
 
 pub fn pencil(event: &Event, state: &mut State) {
     let data = match &mut state.mode {
         super::Mode::Pencil(data) => data,
         _ => unreachable!(),
     };
-    handle_click(event, |_, col, row| {
-        state.virtual_display.set(
-            col,
-            row,
-            Layer {
-                brush: data.pencil,
-                brush_color: state.color,
-                changed: true,
-            },
-        );
-    });
     handle_keychar(event, |code| {
         data.pencil = code;
     });
+    let pencil = data.pencil;
+    handle_click(event, |ev, col, row| {
+        // Fast drags skip cells, so connect to where the mouse was last time
+        let from = match (ev.kind, state.drag_pos) {
+            (MouseEventKind::Down(_), _) => (col, row),
+            (_, Some(pos)) => pos,
+            // The button went down somewhere else, like on the status bar
+            (_, None) => return,
+        };
+        for (col, row) in shapes::line(from, (col, row)) {
+            state.stroke(col, row, pencil);
+        }
+        state.drag_pos = Some((col, row));
+    });
 }
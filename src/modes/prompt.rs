@@ -0,0 +1,27 @@
+use crate::{commands::run_command, data::*, handlers::handle_keyboard};
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+
+use super::Mode;
+
+pub fn prompt(event: &Event, state: &mut State) {
+    handle_keyboard(event, |key| {
+        let data = match &mut state.mode {
+            Mode::Prompt(data) => data,
+            _ => unreachable!(),
+        };
+        match key.code {
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                data.input.push(c);
+            }
+            KeyCode::Backspace => {
+                data.input.pop();
+            }
+            KeyCode::Enter => {
+                let input = std::mem::take(&mut data.input);
+                state.mode = Mode::Command;
+                state.command = run_command(&input, state);
+            }
+            _ => {}
+        }
+    });
+}
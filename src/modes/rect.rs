@@ -0,0 +1,21 @@
+use crate::{data::*, handlers::handle_keyboard, shapes};
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+
+use super::Mode;
+
+pub fn rect(event: &Event, state: &mut State) {
+    let Mode::Rect(mut data) = state.mode.clone() else {
+        unreachable!()
+    };
+    handle_keyboard(event, |key| match key.code {
+        KeyCode::Tab => data.style = data.style.next(),
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => data.fill = Some(c),
+        KeyCode::Backspace => data.fill = None,
+        _ => {}
+    });
+    let (style, fill) = (data.style, data.fill);
+    super::drag_shape(event, state, &mut data.anchor, |from, to| {
+        shapes::rect(from, to, style, fill)
+    });
+    state.mode = Mode::Rect(data);
+}
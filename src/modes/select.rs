@@ -0,0 +1,144 @@
+use std::io::Stdout;
+
+use crate::{
+    clipboard,
+    data::*,
+    export,
+    handlers::{handle_keyboard, handle_mouse},
+    transform,
+};
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind};
+
+use super::{Floating, Mode, SelectData};
+
+pub fn select(event: &Event, stdout: &mut Stdout, state: &mut State) {
+    let Mode::Select(mut data) = state.mode.clone() else {
+        unreachable!()
+    };
+    let canvas = &mut state.virtual_display;
+    handle_keyboard(event, |key| {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return;
+        }
+        let selection = canvas.selection;
+        match (key.code, selection) {
+            (KeyCode::Char('y'), Some(area)) => state.clipboard = canvas.copy(area),
+            // To the system clipboard, as text
+            (KeyCode::Char('Y'), Some(area)) => {
+                clipboard::copy(stdout, &export::area_text(canvas, area));
+                state.command = Command::Copy;
+            }
+            (KeyCode::Char('x'), Some(area)) => {
+                state.clipboard = canvas.copy(area);
+                canvas.erase_area(area);
+            }
+            (KeyCode::Char('p'), _) if !state.clipboard.is_empty() => {
+                data.floating = Some(Floating {
+                    cells: state.clipboard.clone(),
+                    grab: (0, 0),
+                    moving: false,
+                });
+            }
+            (KeyCode::Delete | KeyCode::Backspace, Some(area)) => canvas.erase_area(area),
+            (KeyCode::Char('h'), _) => apply(canvas, &mut data, transform::flip_horizontal),
+            (KeyCode::Char('v'), _) => apply(canvas, &mut data, transform::flip_vertical),
+            (KeyCode::Char('r'), _) => apply(canvas, &mut data, transform::rotate),
+            _ => {}
+        }
+    });
+
+    handle_mouse(event, |ev| {
+        let pos = (ev.column, ev.row);
+        match ev.kind {
+            MouseEventKind::Down(MouseButton::Left) => match &data.floating {
+                // Pasted cells follow the mouse until they're dropped with a click
+                Some(floating) if !floating.moving => {
+                    let floating = data.floating.take().unwrap();
+                    place(canvas, pos, floating);
+                }
+                _ => match canvas.selection {
+                    Some(area) if area.contains(pos.0, pos.1) => {
+                        // Lift the selection off the canvas so it can be dragged around
+                        let cells = canvas.copy(area);
+                        canvas.erase_area(area);
+                        let floating = Floating {
+                            cells,
+                            grab: (pos.0 - area.left, pos.1 - area.top),
+                            moving: true,
+                        };
+                        hover(canvas, pos, &floating);
+                        data.floating = Some(floating);
+                    }
+                    _ => {
+                        data.anchor = Some(pos);
+                        canvas.set_selection(Some(Area::new(pos, pos)));
+                    }
+                },
+            },
+            MouseEventKind::Drag(MouseButton::Left) => {
+                if let Some(floating) = &data.floating {
+                    hover(canvas, pos, floating);
+                } else if let Some(anchor) = data.anchor {
+                    canvas.set_selection(Some(Area::new(anchor, pos)));
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                data.anchor = None;
+                if data
+                    .floating
+                    .as_ref()
+                    .is_some_and(|floating| floating.moving)
+                {
+                    let floating = data.floating.take().unwrap();
+                    place(canvas, pos, floating);
+                }
+            }
+            MouseEventKind::Moved => {
+                if let Some(floating) = &data.floating {
+                    hover(canvas, pos, floating);
+                }
+            }
+            _ => {}
+        }
+    });
+    state.mode = Mode::Select(data);
+}
+
+/// Transforms the cells being pasted or moved if there are any, otherwise the selection
+fn apply(canvas: &mut Canvas, data: &mut SelectData, f: fn(&Clip) -> Clip) {
+    if let Some(floating) = &mut data.floating {
+        floating.cells = f(&floating.cells);
+        // Shown where it was until the mouse moves again
+        if let Some(area) = canvas.selection {
+            canvas.preview_clip(area.left, area.top, &floating.cells);
+            let area = Area::of_clip(area.left, area.top, &floating.cells);
+            canvas.set_selection(Some(area));
+        }
+    } else if let Some(area) = canvas.selection {
+        let cells = f(&canvas.copy(area));
+        canvas.erase_area(area);
+        canvas.paste(area.left, area.top, &cells);
+        canvas.set_selection(Some(Area::of_clip(area.left, area.top, &cells)));
+    }
+}
+
+// Where the top left corner of the floating cells ends up with the mouse at `pos`
+fn corner(pos: (u16, u16), floating: &Floating) -> (u16, u16) {
+    (
+        pos.0.saturating_sub(floating.grab.0),
+        pos.1.saturating_sub(floating.grab.1),
+    )
+}
+
+fn hover(canvas: &mut Canvas, pos: (u16, u16), floating: &Floating) {
+    let (col, row) = corner(pos, floating);
+    canvas.preview_clip(col, row, &floating.cells);
+    canvas.set_selection(Some(Area::of_clip(col, row, &floating.cells)));
+}
+
+fn place(canvas: &mut Canvas, pos: (u16, u16), floating: Floating) {
+    let (col, row) = corner(pos, &floating);
+    canvas.clear_preview();
+    canvas.paste(col, row, &floating.cells);
+    canvas.set_selection(Some(Area::of_clip(col, row, &floating.cells)));
+}
@@ -0,0 +1,100 @@
+//! Character ramps: characters ordered from empty to the most ink, used to shade with text
+
+use std::env;
+
+// The full ASCII ramp the brushes started out with
+const LUMA_VALUES: [char; 92] = [
+    ' ', '`', '.', '-', '\'', ':', '_', ',', '^', '=', ';', '>', '<', '+', '!', 'r', 'c', '*', '/',
+    'z', '?', 's', 'L', 'T', 'v', ')', 'J', '7', '(', '|', 'F', 'i', '{', 'C', '}', 'f', 'I', '3',
+    '1', 't', 'l', 'u', '[', 'n', 'e', 'o', 'Z', '5', 'Y', 'x', 'j', 'y', 'a', ']', '2', 'E', 'S',
+    'w', 'q', 'k', 'P', '6', 'h', '9', 'd', '4', 'V', 'p', 'O', 'G', 'b', 'U', 'A', 'K', 'X', 'H',
+    'm', '8', 'R', 'D', '#', '$', 'B', 'g', '0', 'M', 'N', 'W', 'Q', '%', '&', '@',
+];
+
+/// The built in ramps, besides the full ASCII one
+const NAMED: [(&str, &str); 3] = [
+    ("short", " .:-=+*#%@"),
+    ("blocks", " ░▒▓█"),
+    ("braille", " ⠁⠃⠇⡇⡏⡟⡿⣿"),
+];
+
+/// Brushes work on the scale of the full ramp, so a brush is equally strong with every ramp
+pub const LEVELS: usize = LUMA_VALUES.len() - 1;
+
+/// Environment variable with the ramp to start with, a name or the characters themselves
+const ENV: &str = "TERMDRAW_RAMP";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ramp {
+    pub name: String,
+    pub chars: Vec<char>,
+}
+
+impl Default for Ramp {
+    fn default() -> Self {
+        Ramp {
+            name: "full".to_string(),
+            chars: LUMA_VALUES.to_vec(),
+        }
+    }
+}
+
+impl Ramp {
+    /// A built in ramp by name, or else a ramp of the given characters. Those go from
+    /// light to dark, a space for empty cells gets added in front if it's missing.
+    pub fn parse(input: &str) -> Option<Ramp> {
+        if input == "full" {
+            return Some(Ramp::default());
+        }
+        if let Some((name, chars)) = NAMED.iter().find(|(name, _)| *name == input) {
+            return Some(Ramp {
+                name: name.to_string(),
+                chars: chars.chars().collect(),
+            });
+        }
+        let mut chars: Vec<char> = input.chars().collect();
+        if chars.first() != Some(&' ') {
+            chars.insert(0, ' ');
+        }
+        (chars.len() > 1).then(|| Ramp {
+            name: "custom".to_string(),
+            chars,
+        })
+    }
+
+    /// The ramp from `TERMDRAW_RAMP`, or the full one
+    pub fn from_env() -> Ramp {
+        env::var(ENV)
+            .ok()
+            .and_then(|ramp| Ramp::parse(&ramp))
+            .unwrap_or_default()
+    }
+
+    /// The next built in ramp, for cycling through them
+    pub fn next(&self) -> Ramp {
+        let names: Vec<&str> = std::iter::once("full")
+            .chain(NAMED.iter().map(|(name, _)| *name))
+            .collect();
+        let index = names
+            .iter()
+            .position(|name| *name == self.name)
+            .map_or(0, |index| index + 1);
+        Ramp::parse(names[index % names.len()]).unwrap_or_default()
+    }
+
+    /// How much ink `c` has, from 0 to `LEVELS`, `None` if it's not part of the ramp
+    pub fn level(&self, c: char) -> Option<usize> {
+        let index = self.chars.iter().position(|&other| other == c)?;
+        Some((index * LEVELS + self.steps() / 2) / self.steps())
+    }
+
+    /// The character closest to `level`, which goes from 0 to `LEVELS`
+    pub fn get(&self, level: usize) -> char {
+        let index = (level.min(LEVELS) * self.steps() + LEVELS / 2) / LEVELS;
+        self.chars[index]
+    }
+
+    pub fn steps(&self) -> usize {
+        self.chars.len() - 1
+    }
+}
@@ -0,0 +1,513 @@
+//! Rasterising shapes into the cells they cover
+
+use std::{fmt, fmt::Display};
+
+/// The cells of a straight line from `from` to `to`, both ends included
+pub fn line(from: (u16, u16), to: (u16, u16)) -> Vec<(u16, u16)> {
+    // Bresenham, with the error term covering all octants at once
+    let (mut x, mut y) = (from.0 as i32, from.1 as i32);
+    let (x1, y1) = (to.0 as i32, to.1 as i32);
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let step_x = if x < x1 { 1 } else { -1 };
+    let step_y = if y < y1 { 1 } else { -1 };
+    let mut error = dx + dy;
+    let mut cells = Vec::with_capacity((dx - dy) as usize + 1);
+    loop {
+        cells.push((x as u16, y as u16));
+        if x == x1 && y == y1 {
+            return cells;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// The character that looks most like a line going from `from` to `to`
+pub fn line_char(from: (u16, u16), to: (u16, u16)) -> char {
+    let dx = to.0 as f64 - from.0 as f64;
+    let dy = to.1 as f64 - from.1 as f64;
+    slope_char(dx, dy)
+}
+
+/// The character that looks most like a line going `dx` columns across and `dy` rows down
+fn slope_char(dx: f64, dy: f64) -> char {
+    // Cells are about twice as tall as they are wide
+    let dy = dy * 2.0;
+    if dy.abs() * 2.0 <= dx.abs() {
+        '-'
+    } else if dx.abs() * 2.0 <= dy.abs() {
+        '|'
+    } else if (dx > 0.0) == (dy > 0.0) {
+        '\\'
+    } else {
+        '/'
+    }
+}
+
+/// The set of characters a box is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BoxStyle {
+    Ascii,
+    #[default]
+    Light,
+    Heavy,
+    Double,
+    Rounded,
+}
+
+impl BoxStyle {
+    pub fn next(&self) -> BoxStyle {
+        match self {
+            BoxStyle::Ascii => BoxStyle::Light,
+            BoxStyle::Light => BoxStyle::Heavy,
+            BoxStyle::Heavy => BoxStyle::Double,
+            BoxStyle::Double => BoxStyle::Rounded,
+            BoxStyle::Rounded => BoxStyle::Ascii,
+        }
+    }
+
+    /// Top left, top right, bottom left and bottom right corners, then the horizontal
+    /// and the vertical edge
+    fn chars(&self) -> [char; 6] {
+        match self {
+            BoxStyle::Ascii => ['+', '+', '+', '+', '-', '|'],
+            BoxStyle::Light => ['┌', '┐', '└', '┘', '─', '│'],
+            BoxStyle::Heavy => ['┏', '┓', '┗', '┛', '━', '┃'],
+            BoxStyle::Double => ['╔', '╗', '╚', '╝', '═', '║'],
+            BoxStyle::Rounded => ['╭', '╮', '╰', '╯', '─', '│'],
+        }
+    }
+}
+
+impl Display for BoxStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                BoxStyle::Ascii => "ASCII",
+                BoxStyle::Light => "LIGHT",
+                BoxStyle::Heavy => "HEAVY",
+                BoxStyle::Double => "DOUBLE",
+                BoxStyle::Rounded => "ROUNDED",
+            }
+        )
+    }
+}
+
+/// The cells of a box with opposite corners `from` and `to`, filled with `fill` if given
+pub fn rect(
+    from: (u16, u16),
+    to: (u16, u16),
+    style: BoxStyle,
+    fill: Option<char>,
+) -> Vec<(u16, u16, char)> {
+    let [top_left, top_right, bottom_left, bottom_right, horizontal, vertical] = style.chars();
+    let (left, right) = (from.0.min(to.0), from.0.max(to.0));
+    let (top, bottom) = (from.1.min(to.1), from.1.max(to.1));
+    let mut cells = vec![];
+    for row in top..=bottom {
+        for col in left..=right {
+            let brush = match (col == left || col == right, row == top || row == bottom) {
+                // A box that's a single row or column is just a line
+                _ if top == bottom => horizontal,
+                _ if left == right => vertical,
+                (true, true) => match (col == left, row == top) {
+                    (true, true) => top_left,
+                    (false, true) => top_right,
+                    (true, false) => bottom_left,
+                    (false, false) => bottom_right,
+                },
+                (false, true) => horizontal,
+                (true, false) => vertical,
+                (false, false) => match fill {
+                    Some(fill) => fill,
+                    None => continue,
+                },
+            };
+            cells.push((col, row, brush));
+        }
+    }
+    cells
+}
+
+// How strongly a box-drawing character connects up, right, down and left:
+// 0 is not at all, 1 light, 2 heavy and 3 double. Rounded corners come last so
+// merging them gives the square ones, which are the only ones with junctions.
+const CONNECTIONS: &[(char, [u8; 4])] = &[
+    ('─', [0, 1, 0, 1]),
+    ('━', [0, 2, 0, 2]),
+    ('│', [1, 0, 1, 0]),
+    ('┃', [2, 0, 2, 0]),
+    ('┌', [0, 1, 1, 0]),
+    ('┍', [0, 2, 1, 0]),
+    ('┎', [0, 1, 2, 0]),
+    ('┏', [0, 2, 2, 0]),
+    ('┐', [0, 0, 1, 1]),
+    ('┑', [0, 0, 1, 2]),
+    ('┒', [0, 0, 2, 1]),
+    ('┓', [0, 0, 2, 2]),
+    ('└', [1, 1, 0, 0]),
+    ('┕', [1, 2, 0, 0]),
+    ('┖', [2, 1, 0, 0]),
+    ('┗', [2, 2, 0, 0]),
+    ('┘', [1, 0, 0, 1]),
+    ('┙', [1, 0, 0, 2]),
+    ('┚', [2, 0, 0, 1]),
+    ('┛', [2, 0, 0, 2]),
+    ('├', [1, 1, 1, 0]),
+    ('┝', [1, 2, 1, 0]),
+    ('┞', [2, 1, 1, 0]),
+    ('┟', [1, 1, 2, 0]),
+    ('┠', [2, 1, 2, 0]),
+    ('┡', [2, 2, 1, 0]),
+    ('┢', [1, 2, 2, 0]),
+    ('┣', [2, 2, 2, 0]),
+    ('┤', [1, 0, 1, 1]),
+    ('┥', [1, 0, 1, 2]),
+    ('┦', [2, 0, 1, 1]),
+    ('┧', [1, 0, 2, 1]),
+    ('┨', [2, 0, 2, 1]),
+    ('┩', [2, 0, 1, 2]),
+    ('┪', [1, 0, 2, 2]),
+    ('┫', [2, 0, 2, 2]),
+    ('┬', [0, 1, 1, 1]),
+    ('┭', [0, 1, 1, 2]),
+    ('┮', [0, 2, 1, 1]),
+    ('┯', [0, 2, 1, 2]),
+    ('┰', [0, 1, 2, 1]),
+    ('┱', [0, 1, 2, 2]),
+    ('┲', [0, 2, 2, 1]),
+    ('┳', [0, 2, 2, 2]),
+    ('┴', [1, 1, 0, 1]),
+    ('┵', [1, 1, 0, 2]),
+    ('┶', [1, 2, 0, 1]),
+    ('┷', [1, 2, 0, 2]),
+    ('┸', [2, 1, 0, 1]),
+    ('┹', [2, 1, 0, 2]),
+    ('┺', [2, 2, 0, 1]),
+    ('┻', [2, 2, 0, 2]),
+    ('┼', [1, 1, 1, 1]),
+    ('┽', [1, 1, 1, 2]),
+    ('┾', [1, 2, 1, 1]),
+    ('┿', [1, 2, 1, 2]),
+    ('╀', [2, 1, 1, 1]),
+    ('╁', [1, 1, 2, 1]),
+    ('╂', [2, 1, 2, 1]),
+    ('╃', [2, 1, 1, 2]),
+    ('╄', [2, 2, 1, 1]),
+    ('╅', [1, 1, 2, 2]),
+    ('╆', [1, 2, 2, 1]),
+    ('╇', [2, 2, 1, 2]),
+    ('╈', [1, 2, 2, 2]),
+    ('╉', [2, 1, 2, 2]),
+    ('╊', [2, 2, 2, 1]),
+    ('╋', [2, 2, 2, 2]),
+    ('═', [0, 3, 0, 3]),
+    ('║', [3, 0, 3, 0]),
+    ('╒', [0, 3, 1, 0]),
+    ('╓', [0, 1, 3, 0]),
+    ('╔', [0, 3, 3, 0]),
+    ('╕', [0, 0, 1, 3]),
+    ('╖', [0, 0, 3, 1]),
+    ('╗', [0, 0, 3, 3]),
+    ('╘', [1, 3, 0, 0]),
+    ('╙', [3, 1, 0, 0]),
+    ('╚', [3, 3, 0, 0]),
+    ('╛', [1, 0, 0, 3]),
+    ('╜', [3, 0, 0, 1]),
+    ('╝', [3, 0, 0, 3]),
+    ('╞', [1, 3, 1, 0]),
+    ('╟', [3, 1, 3, 0]),
+    ('╠', [3, 3, 3, 0]),
+    ('╡', [1, 0, 1, 3]),
+    ('╢', [3, 0, 3, 1]),
+    ('╣', [3, 0, 3, 3]),
+    ('╤', [0, 3, 1, 3]),
+    ('╥', [0, 1, 3, 1]),
+    ('╦', [0, 3, 3, 3]),
+    ('╧', [1, 3, 0, 3]),
+    ('╨', [3, 1, 0, 1]),
+    ('╩', [3, 3, 0, 3]),
+    ('╪', [1, 3, 1, 3]),
+    ('╫', [3, 1, 3, 1]),
+    ('╬', [3, 3, 3, 3]),
+    ('╴', [0, 0, 0, 1]),
+    ('╵', [1, 0, 0, 0]),
+    ('╶', [0, 1, 0, 0]),
+    ('╷', [0, 0, 1, 0]),
+    ('╸', [0, 0, 0, 2]),
+    ('╹', [2, 0, 0, 0]),
+    ('╺', [0, 2, 0, 0]),
+    ('╻', [0, 0, 2, 0]),
+    ('╼', [0, 2, 0, 1]),
+    ('╽', [1, 0, 2, 0]),
+    ('╾', [0, 1, 0, 2]),
+    ('╿', [2, 0, 1, 0]),
+    ('╭', [0, 1, 1, 0]),
+    ('╮', [0, 0, 1, 1]),
+    ('╰', [1, 1, 0, 0]),
+    ('╯', [1, 0, 0, 1]),
+];
+
+/// How a box-drawing character connects up, right, down and left
+pub fn connections(c: char) -> Option<[u8; 4]> {
+    CONNECTIONS
+        .iter()
+        .find(|(glyph, _)| *glyph == c)
+        .map(|(_, connections)| *connections)
+}
+
+/// The box-drawing character connecting the given ways
+pub fn glyph(connections: [u8; 4]) -> Option<char> {
+    CONNECTIONS
+        .iter()
+        .find(|(_, c)| *c == connections)
+        .map(|(glyph, _)| *glyph)
+}
+
+/// What drawing `new` over `old` should look like, joining lines and box edges that
+/// cross into a junction like `┼` or `╦`, and braille dots into a single character.
+/// Anything else is simply replaced.
+pub fn merge(old: char, new: char) -> char {
+    const ASCII: &str = "-|+";
+    if old == new {
+        return new;
+    }
+    if let (Some(old), Some(new)) = (braille_dots(old), braille_dots(new)) {
+        return braille_char(old | new);
+    }
+    if ASCII.contains(old) && ASCII.contains(new) {
+        return '+';
+    }
+    let (Some(old), Some(arms)) = (connections(old), connections(new)) else {
+        return new;
+    };
+    // The new glyph wins wherever both go in the same direction
+    let mut merged = [0; 4];
+    for (i, arm) in merged.iter_mut().enumerate() {
+        *arm = if arms[i] != 0 { arms[i] } else { old[i] };
+    }
+    // There are no glyphs mixing double and heavy lines, so fall back to the new style
+    let weight = arms.iter().copied().max().unwrap_or(1);
+    glyph(merged)
+        .or_else(|| glyph(merged.map(|arm| arm.min(1) * weight)))
+        .unwrap_or(new)
+}
+
+/// The bit of each dot in a braille character, by column and then row
+pub const BRAILLE_DOTS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+/// The dots set in a braille character, `None` if it isn't one
+pub fn braille_dots(c: char) -> Option<u8> {
+    let bits = (c as u32).checked_sub(0x2800)?;
+    u8::try_from(bits).ok()
+}
+
+pub fn braille_char(dots: u8) -> char {
+    char::from_u32(0x2800 + dots as u32).unwrap_or(' ')
+}
+
+/// The braille characters showing `dots`, where every cell is 2 dots wide and 4 dots tall
+pub fn braille(dots: &[(u16, u16)]) -> Vec<(u16, u16, char)> {
+    let mut cells: Vec<(u16, u16, u8)> = vec![];
+    for &(x, y) in dots {
+        let (col, row) = (x / 2, y / 4);
+        let bit = BRAILLE_DOTS[(x % 2) as usize][(y % 4) as usize];
+        // Neighbouring dots mostly share a cell, so look from the back
+        match cells
+            .iter_mut()
+            .rev()
+            .find(|cell| (cell.0, cell.1) == (col, row))
+        {
+            Some(cell) => cell.2 |= bit,
+            None => cells.push((col, row, bit)),
+        }
+    }
+    cells
+        .into_iter()
+        .map(|(col, row, bits)| (col, row, braille_char(bits)))
+        .collect()
+}
+
+/// The cells of an ellipse fitting the box with opposite corners `from` and `to`.
+/// The outline follows the curve with `-`, `|`, `/` and `\`, the inside is filled
+/// with `fill` if given.
+pub fn ellipse(from: (u16, u16), to: (u16, u16), fill: Option<char>) -> Vec<(u16, u16, char)> {
+    let (left, right) = (from.0.min(to.0), from.0.max(to.0));
+    let (top, bottom) = (from.1.min(to.1), from.1.max(to.1));
+    // Measured from the middle of the box to the outer edges of its cells
+    let center = (
+        (left as f64 + right as f64) / 2.0,
+        (top as f64 + bottom as f64) / 2.0,
+    );
+    let radius = (
+        (right - left) as f64 / 2.0 + 0.5,
+        (bottom - top) as f64 / 2.0 + 0.5,
+    );
+    let offset = |col: i32, row: i32| (col as f64 - center.0, row as f64 - center.1);
+    let inside = |col: i32, row: i32| {
+        let (x, y) = offset(col, row);
+        (x / radius.0).powi(2) + (y / radius.1).powi(2) <= 1.0
+    };
+
+    let mut cells = vec![];
+    for row in top..=bottom {
+        for col in left..=right {
+            let (c, r) = (col as i32, row as i32);
+            if !inside(c, r) {
+                continue;
+            }
+            let outline = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+                .iter()
+                .any(|(dx, dy)| !inside(c + dx, r + dy));
+            let brush = if outline {
+                // Perpendicular to the gradient of the ellipse's equation
+                let (x, y) = offset(c, r);
+                slope_char(-y / radius.1.powi(2), x / radius.0.powi(2))
+            } else {
+                match fill {
+                    Some(fill) => fill,
+                    None => continue,
+                }
+            };
+            cells.push((col, row, brush));
+        }
+    }
+    cells
+}
+
+/// The cells connected to `start` (without going diagonally) for which `same` holds,
+/// within a `width` by `height` grid. Works a row at a time so big areas stay quick.
+pub fn flood<F>(width: usize, height: usize, start: (u16, u16), same: F) -> Vec<(u16, u16)>
+where
+    F: Fn(usize, usize) -> bool,
+{
+    let (x, y) = (start.0 as usize, start.1 as usize);
+    if x >= width || y >= height {
+        return vec![];
+    }
+    // Row by row, so a scanline is a single slice
+    let mut seen = vec![false; width * height];
+    let mut cells = vec![];
+    let mut seeds = vec![(x, y)];
+    while let Some((x, y)) = seeds.pop() {
+        if seen[y * width + x] || !same(x, y) {
+            continue;
+        }
+        let fits = |col: usize| !seen[y * width + col] && same(col, y);
+        let (mut left, mut right) = (x, x);
+        while left > 0 && fits(left - 1) {
+            left -= 1;
+        }
+        while right + 1 < width && fits(right + 1) {
+            right += 1;
+        }
+        seen[y * width + left..=y * width + right].fill(true);
+        cells.extend((left..=right).map(|col| (col as u16, y as u16)));
+        // One seed per run of matching cells in the rows above and below
+        for row in [y.wrapping_sub(1), y + 1] {
+            if row >= height {
+                continue;
+            }
+            let mut in_run = false;
+            for col in left..=right {
+                let fits = !seen[row * width + col] && same(col, row);
+                if fits && !in_run {
+                    seeds.push((col, row));
+                }
+                in_run = fits;
+            }
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crossings() {
+        assert_eq!(merge('─', '│'), '┼');
+        assert_eq!(merge('━', '┃'), '╋');
+        assert_eq!(merge('═', '║'), '╬');
+        // Mixed weights that have a glyph of their own
+        assert_eq!(merge('─', '┃'), '╂');
+        assert_eq!(merge('═', '│'), '╪');
+    }
+
+    #[test]
+    fn t_junctions() {
+        assert_eq!(merge('┌', '┐'), '┬');
+        assert_eq!(merge('│', '╶'), '├');
+        assert_eq!(merge('┘', '└'), '┴');
+        assert_eq!(merge('╔', '╗'), '╦');
+        assert_eq!(merge('┃', '╺'), '┣');
+    }
+
+    #[test]
+    fn rounded_corners() {
+        // There are no rounded junctions, so these turn square
+        assert_eq!(merge('╭', '─'), '┬');
+        assert_eq!(merge('╰', '╯'), '┴');
+        assert_eq!(merge('╭', '┘'), '┼');
+        assert_eq!(merge('╭', '┌'), '┌');
+        assert_eq!(merge('╭', '╭'), '╭');
+    }
+
+    #[test]
+    fn double_and_heavy() {
+        // No glyph mixes the two, so the one drawn last decides
+        assert_eq!(merge('═', '┃'), '╋');
+        assert_eq!(merge('┃', '═'), '╬');
+        assert_eq!(merge('╔', '┛'), '╋');
+    }
+
+    #[test]
+    fn ascii_and_others() {
+        assert_eq!(merge('-', '|'), '+');
+        assert_eq!(merge('+', '-'), '+');
+        assert_eq!(merge('|', '|'), '|');
+        // Only lines join, everything else is replaced
+        assert_eq!(merge('a', '─'), '─');
+        assert_eq!(merge('─', 'a'), 'a');
+        assert_eq!(merge('-', '│'), '│');
+    }
+
+    #[test]
+    fn connections_table() {
+        for (i, &(c, arms)) in CONNECTIONS.iter().enumerate() {
+            assert!(
+                CONNECTIONS[..i].iter().all(|&(other, _)| other != c),
+                "{} is listed twice",
+                c
+            );
+            assert_eq!(connections(c), Some(arms));
+            // Only the rounded corners share their connections with an earlier glyph
+            if !"╭╮╰╯".contains(c) {
+                assert_eq!(glyph(arms), Some(c));
+            }
+        }
+    }
+
+    #[test]
+    fn ellipse_far_out() {
+        // Adding up coordinates past 32767 used to overflow
+        let near = ellipse((0, 0), (2, 2), None);
+        let far = ellipse((40000, 60000), (40002, 60002), None);
+        let moved: Vec<_> = near
+            .iter()
+            .map(|&(col, row, brush)| (col + 40000, row + 60000, brush))
+            .collect();
+        assert_eq!(far, moved);
+    }
+}
@@ -0,0 +1,208 @@
+//! Flipping and rotating cells. Characters that point somewhere get swapped for
+//! their mirror image, so a flipped `/` becomes `\` and not just the same `/`.
+
+use crate::{data::Clip, shapes};
+
+// Characters that turn into each other when mirrored left to right
+const MIRROR_HORIZONTAL: &[(char, char)] = &[
+    ('/', '\\'),
+    ('(', ')'),
+    ('<', '>'),
+    ('[', ']'),
+    ('{', '}'),
+    ('d', 'b'),
+    ('p', 'q'),
+    ('▌', '▐'),
+    ('▖', '▗'),
+    ('▘', '▝'),
+    ('▛', '▜'),
+    ('▙', '▟'),
+    ('▚', '▞'),
+    ('╭', '╮'),
+    ('╰', '╯'),
+];
+
+// Characters that turn into each other when mirrored top to bottom
+const MIRROR_VERTICAL: &[(char, char)] = &[
+    ('/', '\\'),
+    ('^', 'v'),
+    ('\'', ','),
+    ('b', 'p'),
+    ('d', 'q'),
+    ('▀', '▄'),
+    ('▘', '▖'),
+    ('▝', '▗'),
+    ('▛', '▙'),
+    ('▜', '▟'),
+    ('▚', '▞'),
+    ('╭', '╰'),
+    ('╮', '╯'),
+];
+
+// Each character turns into the next one when rotated a quarter turn clockwise
+const ROTATE: &[&[char]] = &[
+    &['-', '|'],
+    &['/', '\\'],
+    &['<', '^', '>', 'v'],
+    &['▌', '▀', '▐', '▄'],
+    &['▖', '▘', '▝', '▗'],
+    &['▙', '▛', '▜', '▟'],
+    &['▚', '▞'],
+    &['╭', '╮', '╯', '╰'],
+];
+
+pub fn flip_horizontal(cells: &Clip) -> Clip {
+    let mut cells = cells.clone();
+    cells.reverse();
+    remap(&mut cells, |c| {
+        swap(MIRROR_HORIZONTAL, c).or_else(|| {
+            let [up, right, down, left] = shapes::connections(c)?;
+            shapes::glyph([up, left, down, right])
+        })
+    });
+    cells
+}
+
+pub fn flip_vertical(cells: &Clip) -> Clip {
+    let mut cells = cells.clone();
+    for column in cells.iter_mut() {
+        column.reverse();
+    }
+    remap(&mut cells, |c| {
+        swap(MIRROR_VERTICAL, c).or_else(|| {
+            let [up, right, down, left] = shapes::connections(c)?;
+            shapes::glyph([down, right, up, left])
+        })
+    });
+    cells
+}
+
+/// Turns the cells a quarter turn clockwise
+pub fn rotate(cells: &Clip) -> Clip {
+    let height = cells.first().map_or(0, Vec::len);
+    // The old bottom row becomes the first column
+    let mut rotated: Clip = (0..height)
+        .map(|col| {
+            cells
+                .iter()
+                .map(|column| column[height - 1 - col])
+                .collect()
+        })
+        .collect();
+    remap(&mut rotated, |c| {
+        ROTATE
+            .iter()
+            .find_map(|cycle| {
+                let index = cycle.iter().position(|&other| other == c)?;
+                Some(cycle[(index + 1) % cycle.len()])
+            })
+            .or_else(|| {
+                let [up, right, down, left] = shapes::connections(c)?;
+                shapes::glyph([left, up, right, down])
+            })
+    });
+    rotated
+}
+
+fn swap(pairs: &[(char, char)], c: char) -> Option<char> {
+    pairs.iter().find_map(|&(a, b)| match c {
+        _ if c == a => Some(b),
+        _ if c == b => Some(a),
+        _ => None,
+    })
+}
+
+fn remap<F>(cells: &mut Clip, f: F)
+where
+    F: Fn(char) -> Option<char>,
+{
+    for cell in cells.iter_mut().flatten().flatten() {
+        if let Some(brush) = f(cell.brush) {
+            cell.brush = brush;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Layer;
+
+    // Clips are column by column, these helpers go row by row so the tests read like text
+    fn clip(rows: &[&str]) -> Clip {
+        let rows: Vec<Vec<char>> = rows.iter().map(|row| row.chars().collect()).collect();
+        (0..rows[0].len())
+            .map(|col| {
+                rows.iter()
+                    .map(|row| {
+                        Some(Layer {
+                            brush: row[col],
+                            ..Default::default()
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn text(cells: &Clip) -> Vec<String> {
+        let height = cells.first().map_or(0, Vec::len);
+        (0..height)
+            .map(|row| {
+                cells
+                    .iter()
+                    .map(|column| column[row].map_or(' ', |cell| cell.brush))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Every character that gets remapped, as a single row
+    fn everything() -> String {
+        let tables = MIRROR_HORIZONTAL
+            .iter()
+            .chain(MIRROR_VERTICAL)
+            .flat_map(|&(a, b)| [a, b])
+            .chain(ROTATE.iter().flat_map(|cycle| cycle.iter().copied()));
+        let boxes = ('─'..='╿').filter(|&c| shapes::connections(c).is_some());
+        tables.chain(boxes).collect()
+    }
+
+    #[test]
+    fn four_rotations() {
+        for rows in [vec!["12", "34", "56"], vec![everything().as_str()]] {
+            let original = clip(&rows);
+            let turned = (0..4).fold(original.clone(), |cells, _| rotate(&cells));
+            assert_eq!(text(&turned), text(&original));
+        }
+    }
+
+    #[test]
+    fn flip_twice() {
+        let original = clip(&[&everything()]);
+        assert_eq!(
+            text(&flip_horizontal(&flip_horizontal(&original))),
+            text(&original)
+        );
+        assert_eq!(
+            text(&flip_vertical(&flip_vertical(&original))),
+            text(&original)
+        );
+    }
+
+    #[test]
+    fn mirrored_characters() {
+        assert_eq!(text(&flip_horizontal(&clip(&["/┌"]))), ["┐\\"]);
+        assert_eq!(text(&flip_vertical(&clip(&["/", "┌"]))), ["└", "\\"]);
+        assert_eq!(text(&rotate(&clip(&["▙"]))), ["▛"]);
+        assert_eq!(text(&rotate(&clip(&["╭─"]))), ["╮", "│"]);
+    }
+
+    #[test]
+    fn non_square() {
+        let cells = clip(&["12", "34", "56"]);
+        assert_eq!(text(&rotate(&cells)), ["531", "642"]);
+        assert_eq!(text(&flip_horizontal(&cells)), ["21", "43", "65"]);
+        assert_eq!(text(&flip_vertical(&cells)), ["56", "34", "12"]);
+    }
+}